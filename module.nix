@@ -2,6 +2,7 @@
 
 let
   cfg = config.dd-ix.website-content-api;
+  settingsFormat = pkgs.formats.toml { };
 in
 {
   options.dd-ix.website-content-api = {
@@ -47,14 +48,46 @@ in
       type = lib.types.str;
       description = ''base url of ixp manager'';
     };
+    corsOrigins = lib.mkOption {
+      type = lib.types.listOf lib.types.str;
+      default = [ "https://dd-ix.net" "https://*.dd-ix.net" ];
+      description = ''
+        Origins allowed to call the api, `https://*.example.com` allows every
+        subdomain and `*` every origin.
+      '';
+    };
+    settings = lib.mkOption {
+      type = settingsFormat.type;
+      default = { };
+      description = ''
+        Additional configuration like refresh intervals, stats queries and
+        route servers, see `website-content-api print-config`.
+      '';
+    };
+    socketActivation = lib.mkOption {
+      type = lib.types.bool;
+      default = false;
+      description = ''
+        Let systemd own the listening socket, so that connections are queued
+        instead of refused while the service restarts.
+      '';
+    };
   };
 
   config = lib.mkIf cfg.enable {
+    systemd.sockets.ddix-website-content-api = lib.mkIf cfg.socketActivation {
+      description = "DD-IX Website Content API Socket";
+
+      wantedBy = [ "sockets.target" ];
+      listenStreams = [ "${cfg.http.host}:${toString cfg.http.port}" ];
+    };
+
     systemd.services.ddix-website-content-api = {
       description = "DD-IX Website Content API";
 
       wantedBy = [ "multi-user.target" ];
       after = [ "network.target" ];
+      requires = lib.mkIf cfg.socketActivation [ "ddix-website-content-api.socket" ];
 
       environment = {
         WEBSITE_CONTENT_API_LISTEN_ADDR = "${cfg.http.host}:${toString cfg.http.port}";
@@ -63,12 +96,24 @@ in
         #WEBSITE_CONTENT_API_LOOKING_GLASS_URL = cfg.lookingGlassUrl;
         WEBSITE_CONTENT_API_PROMETHEUS_URL = cfg.prometheusUrl;
         WEBSITE_CONTENT_API_IXP_MANAGER_URL = cfg.ixpManagerUrl;
+        WEBSITE_CONTENT_API_CORS_ORIGINS = lib.concatStringsSep "," cfg.corsOrigins;
+        # matches StateDirectory, systemd does not expand $STATE_DIRECTORY here
+        WEBSITE_CONTENT_API_STATE_DIRECTORY = "/var/lib/website-content-api/cache";
+      } // lib.optionalAttrs (cfg.settings != { }) {
+        WEBSITE_CONTENT_API_CONFIG = settingsFormat.generate "website-content-api.toml" cfg.settings;
       };
 
       serviceConfig = {
+        Type = "notify";
         ExecStart = lib.getExe cfg.package;
         DynamicUser = true;
+        # only the cache snapshots are owned by the dynamic user, the parent
+        # directory stays with root, so that the externally written bird.html
+        # next to it remains reachable for its writer
+        StateDirectory = "website-content-api/cache";
         Restart = "always";
+        WatchdogSec = "30s";
+        TimeoutStopSec = "30s";
       };
     };
 
@@ -124,9 +169,21 @@ in
             access_log off;
           '';
         };
+        # cache errors and metrics name upstream hosts and routes, only probes
+        # and scrapers on the host itself may read them, directly from the api
+        # or the listener set in `settings.metrics_listen_addr`
+        "~ ^/(readyz|status|metrics)$" = {
+          extraConfig = ''
+            deny all;
+          '';
+        };
         "/" = {
           recommendedProxySettings = true;
           proxyPass = "http://${cfg.http.host}:${toString cfg.http.port}/";
+          # the api only reads X-Forwarded-For, never pass on a client's header
+          extraConfig = ''
+            proxy_set_header Forwarded "";
+          '';
         };
       };
     };
@@ -1,60 +1,111 @@
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use ipnet::IpNet;
 use url::Url;
 
+use crate::config::{ForwardedHeader, LogFormat};
+
+/// Flags and environment variables override the config file, unset ones keep
+/// the value of the config file or its default.
 #[derive(Parser)]
 pub(crate) struct Args {
-  #[clap(
-    short,
-    long,
-    env = "WEBSITE_CONTENT_API_LISTEN_ADDR",
-    default_value = "127.0.0.1:8080"
-  )]
-  pub(crate) listen_addr: SocketAddr,
+  #[clap(subcommand)]
+  pub(crate) command: Option<Command>,
 
-  #[clap(
-    short,
-    long,
-    env = "WEBSITE_CONTENT_API_CONTENT_DIRECTORY",
-    default_value = "content"
-  )]
-  pub(crate) content_directory: PathBuf,
+  /// TOML or YAML file with the configuration, see `print-config`
+  #[clap(long, env = "WEBSITE_CONTENT_API_CONFIG")]
+  pub(crate) config: Option<PathBuf>,
 
-  #[clap(
-    short,
-    long,
-    env = "WEBSITE_CONTENT_API_BASE_URL",
-    default_value = "http://localhost:8080/"
-  )]
-  pub(crate) base_url: Url,
+  #[clap(short, long, env = "WEBSITE_CONTENT_API_LISTEN_ADDR")]
+  pub(crate) listen_addr: Option<SocketAddr>,
 
-  #[clap(
-    long,
-    env = "WEBSITE_CONTENT_API_PROMETHEUS_URL",
-    default_value = "https://svc-prom01.dd-ix.net/"
-  )]
-  pub(crate) prometheus_url: Url,
+  /// Separate address to serve `/metrics` on instead of the api, so that it
+  /// is not exposed through the public site
+  #[clap(long, env = "WEBSITE_CONTENT_API_METRICS_LISTEN_ADDR")]
+  pub(crate) metrics_listen_addr: Option<SocketAddr>,
 
-  #[clap(
-    long,
-    env = "WEBSITE_CONTENT_API_IXP_MANAGER_URL",
-    default_value = "https://portal.dd-ix.net/"
-  )]
-  pub(crate) ixp_manager_url: Url,
+  #[clap(short, long, env = "WEBSITE_CONTENT_API_CONTENT_DIRECTORY")]
+  pub(crate) content_directory: Option<PathBuf>,
 
+  #[clap(short, long, env = "WEBSITE_CONTENT_API_BASE_URL")]
+  pub(crate) base_url: Option<Url>,
+
+  #[clap(long, env = "WEBSITE_CONTENT_API_PROMETHEUS_URL")]
+  pub(crate) prometheus_url: Option<Url>,
+
+  #[clap(long, env = "WEBSITE_CONTENT_API_IXP_MANAGER_URL")]
+  pub(crate) ixp_manager_url: Option<Url>,
+
+  #[clap(long, env = "WEBSITE_CONTENT_API_LOOKING_GLASS_URL")]
+  pub(crate) looking_glass_url: Option<Url>,
+
+  #[clap(long, env = "WEBSITE_CONTENT_API_BIRD_HTML")]
+  pub(crate) bird_html: Option<PathBuf>,
+
+  /// Language codes content is written in, every post, text block, document
+  /// list and team description is expected in each of them
+  #[clap(long, env = "WEBSITE_CONTENT_API_LANGUAGES", value_delimiter = ',')]
+  pub(crate) languages: Option<Vec<String>>,
+
+  /// Directory the upstream caches are persisted to, so that they can be
+  /// served right after a restart
+  #[clap(long, env = "WEBSITE_CONTENT_API_STATE_DIRECTORY")]
+  pub(crate) state_directory: Option<PathBuf>,
+
+  /// Languages tried in order when content is missing in the requested one,
+  /// defaults to the order of `--languages`
   #[clap(
     long,
-    env = "WEBSITE_CONTENT_API_LOOKING_GLASS_URL",
-    default_value = "https://lg.dd-ix.net/"
+    env = "WEBSITE_CONTENT_API_FALLBACK_LANGUAGES",
+    value_delimiter = ','
   )]
-  pub(crate) looking_glass_url: Url,
+  pub(crate) fallback_languages: Option<Vec<String>>,
+
+  /// Token granting access to drafts and scheduled posts via `/preview`
+  #[clap(long, env = "WEBSITE_CONTENT_API_PREVIEW_TOKEN")]
+  pub(crate) preview_token: Option<String>,
 
+  /// Networks of reverse proxies trusted to name the client in the forwarded
+  /// header
   #[clap(
     long,
-    env = "WEBSITE_CONTENT_API_BIRD_HTML",
-    default_value = "/var/lib/website-content-api/bird.html"
+    env = "WEBSITE_CONTENT_API_TRUSTED_PROXIES",
+    value_delimiter = ','
   )]
-  pub(crate) bird_html: PathBuf,
+  pub(crate) trusted_proxies: Option<Vec<IpNet>>,
+
+  /// Header the trusted proxies name the client in
+  #[clap(long, env = "WEBSITE_CONTENT_API_FORWARDED_HEADER")]
+  pub(crate) forwarded_header: Option<ForwardedHeader>,
+
+  /// Origins allowed to call the api, e.g. `https://*.dd-ix.net`, `*` allows
+  /// every origin
+  #[clap(long, env = "WEBSITE_CONTENT_API_CORS_ORIGINS", value_delimiter = ',')]
+  pub(crate) cors_origins: Option<Vec<String>>,
+
+  /// Seconds browsers and proxies may cache assets and document downloads
+  #[clap(long, env = "WEBSITE_CONTENT_API_ASSETS_MAX_AGE")]
+  pub(crate) assets_max_age: Option<u64>,
+
+  /// Seconds browsers and proxies may cache posts, text blocks and other
+  /// content, upstream data is cached until its next refresh instead
+  #[clap(long, env = "WEBSITE_CONTENT_API_CONTENT_MAX_AGE")]
+  pub(crate) content_max_age: Option<u64>,
+
+  #[clap(long, env = "WEBSITE_CONTENT_API_LOG_FORMAT")]
+  pub(crate) log_format: Option<LogFormat>,
+
+  /// Log levels per module, e.g. `info,website_content_api::cache=debug`
+  #[clap(long, env = "WEBSITE_CONTENT_API_LOG_FILTER")]
+  pub(crate) log_filter: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Command {
+  /// Check the content directory for problems and exit
+  Validate,
+  /// Print the effective configuration as TOML and exit
+  PrintConfig,
 }
@@ -2,7 +2,8 @@ use select::predicate::Name;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::cache::{Cache, Updater};
+use crate::cache::{Cache, CacheStatus, RefreshPolicy, Updater};
+use crate::config::BirdConfig;
 
 struct BirdUpdater {
   path: PathBuf,
@@ -24,14 +25,23 @@ impl Updater for BirdUpdater {
 }
 
 impl Bird {
-  pub(crate) async fn new(path: PathBuf) -> anyhow::Result<Self> {
+  pub(crate) async fn new(config: &BirdConfig) -> anyhow::Result<Self> {
+    let updater = BirdUpdater {
+      path: config.html.clone(),
+    };
+    let refresh = RefreshPolicy::every(config.refresh_interval);
+
     Ok(Self {
-      content: Arc::new(Cache::new(BirdUpdater { path })),
+      content: Arc::new(Cache::new("bird", refresh, updater)),
     })
   }
 
+  pub(crate) fn status(&self) -> CacheStatus {
+    self.content.status()
+  }
+
   pub(crate) async fn content(&self) -> anyhow::Result<Arc<String>> {
-    self.content.get().await
+    Ok(self.content.get().await?.value)
   }
 }
 
@@ -2,10 +2,12 @@ use std::sync::Arc;
 
 use crate::lang::Language;
 use crate::posts::{
-  post_provider::{LongPostFromMeta, PostMeta, PostProvider},
-  MyDate,
+  feed::FeedPost,
+  post_provider::{LongPostFromMeta, PostContent, PostMeta, PostProvider, Translatable},
+  Alternates, MyDate, Publication,
 };
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 pub type Blogs = PostProvider<BlogMeta, SmallBlogPost, BlogPost>;
 
@@ -18,6 +20,9 @@ pub(crate) struct BlogMeta {
   keywords: Vec<String>,
   authors: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
+  translation_key: Option<String>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -32,6 +37,11 @@ pub(crate) struct BlogPost {
   keywords: Vec<String>,
   authors: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
+  #[serde(skip)]
+  translation_key: Option<String>,
+  alternates: Alternates,
   body: String,
 }
 
@@ -47,6 +57,8 @@ pub(crate) struct SmallBlogPost {
   keywords: Vec<String>,
   authors: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
 }
 
 impl LongPostFromMeta<BlogMeta> for BlogPost {
@@ -62,6 +74,9 @@ impl LongPostFromMeta<BlogMeta> for BlogPost {
       keywords: meta.keywords,
       authors: meta.authors,
       image: meta.image,
+      publication: meta.publication,
+      translation_key: meta.translation_key,
+      alternates: Alternates::new(),
       body,
     }
   }
@@ -80,6 +95,7 @@ impl From<Arc<BlogPost>> for SmallBlogPost {
       keywords: post.keywords.clone(),
       authors: post.authors.clone(),
       image: post.image.clone(),
+      publication: post.publication,
     }
   }
 }
@@ -100,6 +116,32 @@ impl PostMeta for BlogPost {
   fn keywords(&self) -> &Vec<String> {
     &self.keywords
   }
+
+  fn publication(&self) -> Publication {
+    self.publication
+  }
+
+  fn date(&self) -> OffsetDateTime {
+    self.published.midnight_utc()
+  }
+
+  fn last_modified(&self) -> Option<OffsetDateTime> {
+    Some(self.modified.unwrap_or(self.published).midnight_utc())
+  }
+}
+
+impl Translatable for BlogPost {
+  fn explicit_translation_key(&self) -> Option<&str> {
+    self.translation_key.as_deref()
+  }
+
+  fn alternates(&self) -> &Alternates {
+    &self.alternates
+  }
+
+  fn set_alternates(&mut self, alternates: Alternates) {
+    self.alternates = alternates;
+  }
 }
 
 impl PostMeta for SmallBlogPost {
@@ -118,4 +160,44 @@ impl PostMeta for SmallBlogPost {
   fn keywords(&self) -> &Vec<String> {
     &self.keywords
   }
+
+  fn publication(&self) -> Publication {
+    self.publication
+  }
+
+  fn date(&self) -> OffsetDateTime {
+    self.published.midnight_utc()
+  }
+
+  fn last_modified(&self) -> Option<OffsetDateTime> {
+    Some(self.modified.unwrap_or(self.published).midnight_utc())
+  }
+}
+
+impl PostContent for BlogPost {
+  fn title(&self) -> &str {
+    &self.title
+  }
+
+  fn description(&self) -> &str {
+    &self.description
+  }
+
+  fn body(&self) -> &str {
+    &self.body
+  }
+}
+
+impl FeedPost for BlogPost {
+  fn published(&self) -> MyDate {
+    self.published
+  }
+
+  fn modified(&self) -> Option<MyDate> {
+    self.modified
+  }
+
+  fn authors(&self) -> &[String] {
+    &self.authors
+  }
 }
@@ -1,71 +1,465 @@
-use std::sync::{
-  atomic::{AtomicBool, Ordering},
-  Arc,
-};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use time::{Duration, OffsetDateTime};
-use tokio::sync::RwLock;
+use anyhow::anyhow;
+use metrics::{counter, histogram};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, info_span, warn, Instrument};
 
-const UPDATING_WAIT: std::time::Duration = std::time::Duration::from_millis(10);
-const MAX_AGE: Duration = Duration::minutes(10);
+use crate::conditional::{ETag, Validators};
 
 pub(crate) trait Updater {
   type Output;
-  type Error;
-  async fn update(&self) -> Result<Self::Output, Self::Error>;
+  type Error: std::fmt::Display + Send;
+  fn update(&self) -> impl Future<Output = Result<Self::Output, Self::Error>> + Send;
 }
 
+/// How often a cache is refreshed and how long to wait before retrying after
+/// a failed refresh. The retry delay doubles with every consecutive failure.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RefreshPolicy {
+  pub(crate) interval: Duration,
+  pub(crate) min_backoff: Duration,
+  pub(crate) max_backoff: Duration,
+}
+
+impl RefreshPolicy {
+  pub(crate) const fn every(interval: Duration) -> Self {
+    Self {
+      interval,
+      min_backoff: Duration::from_secs(10),
+      max_backoff: interval,
+    }
+  }
+
+  fn backoff(&self, failures: u32) -> Duration {
+    self
+      .min_backoff
+      .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+      .min(self.max_backoff)
+  }
+}
+
+/// A cached value together with its validators and whether it is stale, i.e.
+/// restored from the state directory and not refreshed since.
+pub(crate) struct Cached<T> {
+  pub(crate) value: T,
+  pub(crate) validators: Validators,
+  pub(crate) stale: bool,
+  /// Time until the value is due to be refreshed.
+  pub(crate) max_age: Duration,
+}
+
+impl<T> Cached<T> {
+  pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> Cached<U> {
+    Cached {
+      value: f(self.value),
+      validators: self.validators,
+      stale: self.stale,
+      max_age: self.max_age,
+    }
+  }
+}
+
+/// Health of a single cache as reported by `/status`.
+#[derive(Serialize)]
+pub(crate) struct CacheStatus {
+  name: String,
+  #[serde(with = "time::serde::rfc3339::option")]
+  last_success: Option<OffsetDateTime>,
+  last_error: Option<String>,
+  age_seconds: Option<u64>,
+  refresh_duration_seconds: Option<f64>,
+  failures: u32,
+  stale: bool,
+  refreshing: bool,
+  background: bool,
+}
+
+impl CacheStatus {
+  /// Caches refreshed in the background have to be filled before the service
+  /// is ready, lazy caches are filled by the first request.
+  pub(crate) fn is_ready(&self) -> bool {
+    !self.background || self.last_success.is_some()
+  }
+
+  pub(crate) fn is_healthy(&self) -> bool {
+    self.last_error.is_none()
+  }
+}
+
+struct Entry<T> {
+  value: Arc<T>,
+  fetched_at: Instant,
+  updated_at: OffsetDateTime,
+  /// Restored from the state directory and not refreshed since.
+  restored: bool,
+}
+
+/// File in the state directory the last value of a cache is persisted to.
+struct StateFile<T> {
+  path: PathBuf,
+  encode: fn(&T, OffsetDateTime) -> serde_json::Result<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Persisted<T> {
+  #[serde(with = "time::serde::rfc3339")]
+  fetched_at: OffsetDateTime,
+  value: T,
+}
+
+struct CacheState<T> {
+  entry: Option<Entry<T>>,
+  refreshing: bool,
+  failures: u32,
+  last_error: Option<String>,
+  retry_at: Option<Instant>,
+  refresh_duration: Option<Duration>,
+  /// Refreshed by a background refresher instead of on access.
+  background: bool,
+}
+
+/// Value fetched from an upstream source, refreshed either lazily on access
+/// or by a background refresher.
+///
+/// Stale values are served while a refresh runs in the background and
+/// concurrent requests for an empty cache wait for a single shared refresh.
 pub(crate) struct Cache<U: Updater> {
-  updating: AtomicBool,
-  cached: RwLock<Option<(OffsetDateTime, Arc<U::Output>)>>,
+  name: String,
+  policy: RefreshPolicy,
+  state: Mutex<CacheState<U::Output>>,
+  refreshed: Notify,
+  state_file: Option<StateFile<U::Output>>,
   updater: U,
 }
 
-impl<U: Updater> Cache<U> {
-  pub(crate) fn new(updater: U) -> Self {
+impl<U> Cache<U>
+where
+  U: Updater + Send + Sync + 'static,
+  U::Output: Send + Sync,
+{
+  pub(crate) fn new(name: impl Into<String>, policy: RefreshPolicy, updater: U) -> Self {
     Self {
-      updating: AtomicBool::new(false),
-      cached: RwLock::new(None),
+      name: name.into(),
+      policy,
+      state: Mutex::new(CacheState {
+        entry: None,
+        refreshing: false,
+        failures: 0,
+        last_error: None,
+        retry_at: None,
+        refresh_duration: None,
+        background: false,
+      }),
+      refreshed: Notify::new(),
+      state_file: None,
       updater,
     }
   }
 
-  pub(crate) async fn get_cached(&self) -> Option<Arc<U::Output>> {
-    match self.cached.read().await.clone().map(|x| x.1.clone()) {
-      None => None,
-      Some(x) => Some(x),
+  pub(crate) fn status(&self) -> CacheStatus {
+    let state = self.state.lock().unwrap();
+    CacheStatus {
+      name: self.name.clone(),
+      last_success: state.entry.as_ref().map(|entry| entry.updated_at),
+      last_error: state.last_error.clone(),
+      age_seconds: state
+        .entry
+        .as_ref()
+        .map(|entry| entry.fetched_at.elapsed().as_secs()),
+      refresh_duration_seconds: state
+        .refresh_duration
+        .map(|duration| duration.as_secs_f64()),
+      failures: state.failures,
+      stale: state.entry.as_ref().is_some_and(|entry| entry.restored),
+      refreshing: state.refreshing,
+      background: state.background,
     }
   }
 
-  pub(crate) async fn get(&self) -> Result<Arc<U::Output>, U::Error> {
-    let now = OffsetDateTime::now_utc();
+  /// Returns the cached value, even if it is stale, without triggering a
+  /// refresh.
+  pub(crate) fn get_cached(&self) -> Option<Cached<Arc<U::Output>>> {
+    let state = self.state.lock().unwrap();
+    let value = state.entry.as_ref().map(|entry| self.cached(entry));
+    self.record_lookup(if value.is_some() { "hit" } else { "miss" });
+    value
+  }
+
+  /// Returns the cached value. A stale value is returned right away while it
+  /// is refreshed in the background, an empty cache waits for the refresh.
+  pub(crate) async fn get(self: &Arc<Self>) -> anyhow::Result<Cached<Arc<U::Output>>> {
+    let refreshed = {
+      let mut state = self.state.lock().unwrap();
+      let now = Instant::now();
+      let backing_off = state.retry_at.is_some_and(|retry_at| now < retry_at);
+
+      if let Some(entry) = &state.entry {
+        let value = self.cached(entry);
+        let expired = entry.restored || now >= entry.fetched_at + self.policy.interval;
+        self.record_lookup(if expired { "stale" } else { "hit" });
+        if expired && !state.refreshing && !backing_off {
+          self.spawn_refresh(&mut state);
+        }
+        return Ok(value);
+      }
+
+      self.record_lookup("miss");
+
+      if backing_off {
+        return Err(self.last_error(&state));
+      }
+
+      // register before starting the refresh, so the wakeup cannot be missed
+      let refreshed = self.refreshed.notified();
+      if !state.refreshing {
+        self.spawn_refresh(&mut state);
+      }
+      refreshed
+    };
+
+    refreshed.await;
 
-    {
-      while self.updating.load(Ordering::Relaxed) {
-        tokio::time::sleep(UPDATING_WAIT).await
+    let state = self.state.lock().unwrap();
+    match (&state.entry, &state.last_error) {
+      (Some(entry), None) => Ok(self.cached(entry)),
+      _ => Err(self.last_error(&state)),
+    }
+  }
+
+  /// Refreshes the cache, joining a refresh that is already running.
+  pub(crate) async fn refresh(self: &Arc<Self>) {
+    let refreshed = {
+      let mut state = self.state.lock().unwrap();
+      let refreshed = self.refreshed.notified();
+      if !state.refreshing {
+        self.spawn_refresh(&mut state);
       }
+      refreshed
+    };
+
+    refreshed.await;
+  }
+
+  /// Keeps the cache warm by refreshing it whenever it becomes stale, backing
+  /// off exponentially while the upstream source fails, until `shutdown` is
+  /// cancelled.
+  pub(crate) fn spawn_refresher(self: &Arc<Self>, shutdown: &CancellationToken) {
+    self.state.lock().unwrap().background = true;
+
+    let cache = self.clone();
+    let shutdown = shutdown.clone();
+    tokio::spawn(async move {
+      shutdown
+        .run_until_cancelled(async {
+          loop {
+            cache.refresh().await;
+            tokio::time::sleep_until(cache.next_refresh()).await;
+          }
+        })
+        .await;
+    });
+  }
+
+  fn cached(&self, entry: &Entry<U::Output>) -> Cached<Arc<U::Output>> {
+    // every successful refresh starts a new generation of the cache
+    let generation = entry.updated_at.unix_timestamp_nanos();
+    let max_age = if entry.restored {
+      Duration::ZERO
+    } else {
+      self
+        .policy
+        .interval
+        .saturating_sub(entry.fetched_at.elapsed())
+    };
+
+    Cached {
+      value: entry.value.clone(),
+      validators: Validators {
+        etag: ETag::of((&self.name, generation)),
+        last_modified: Some(entry.updated_at),
+      },
+      stale: entry.restored,
+      max_age,
+    }
+  }
+
+  fn next_refresh(&self) -> Instant {
+    let state = self.state.lock().unwrap();
+    state
+      .retry_at
+      .or_else(|| {
+        state
+          .entry
+          .as_ref()
+          .filter(|entry| !entry.restored)
+          .map(|entry| entry.fetched_at + self.policy.interval)
+      })
+      .unwrap_or_else(|| Instant::now() + self.policy.min_backoff)
+  }
+
+  fn spawn_refresh(self: &Arc<Self>, state: &mut CacheState<U::Output>) {
+    state.refreshing = true;
+
+    let cache = self.clone();
+    let span = info_span!(parent: None, "refresh", cache = %self.name);
+    // the refresh runs in its own task, so it completes even if the request
+    // that started it is cancelled
+    tokio::spawn(
+      async move {
+        let started = Instant::now();
+        // the update runs in a task of its own, so that a panic is handled as
+        // a failed refresh instead of leaving the cache refreshing forever
+        let updater = cache.clone();
+        let result = match tokio::spawn(async move { updater.updater.update().await }).await {
+          Ok(result) => result.map(Arc::new).map_err(|err| format!("{:#}", err)),
+          Err(err) => Err(format!("Update failed: {}", err)),
+        };
+        let fetched_at = OffsetDateTime::now_utc();
+
+        {
+          let mut state = cache.state.lock().unwrap();
+          state.refreshing = false;
+          state.refresh_duration = Some(started.elapsed());
+          histogram!("upstream_fetch_duration_seconds", "cache" => cache.name.clone())
+            .record(started.elapsed().as_secs_f64());
+          counter!(
+            "cache_refreshes_total",
+            "cache" => cache.name.clone(),
+            "result" => if result.is_ok() { "success" } else { "failure" },
+          )
+          .increment(1);
+
+          match &result {
+            Ok(value) => {
+              state.entry = Some(Entry {
+                value: value.clone(),
+                fetched_at: Instant::now(),
+                updated_at: fetched_at,
+                restored: false,
+              });
+              state.failures = 0;
+              state.last_error = None;
+              state.retry_at = None;
+              info!("Refreshed {} cache", cache.name);
+            }
+            Err(err) => {
+              state.failures += 1;
+              let backoff = cache.policy.backoff(state.failures);
+              error!(
+                "Failed to refresh {} cache, retrying in {:?}: {}",
+                cache.name, backoff, err
+              );
+              state.last_error = Some(err.clone());
+              state.retry_at = Some(Instant::now() + backoff);
+            }
+          }
+        }
 
-      let lock = self.cached.read().await;
-      if let Some((next_update, stats)) = lock.as_ref() {
-        if next_update < &now {
-          self.updating.store(true, Ordering::Relaxed);
-        } else {
-          return Ok(stats.clone());
+        cache.refreshed.notify_waiters();
+
+        if let (Ok(value), Some(state_file)) = (result, &cache.state_file) {
+          if let Err(err) = state_file.save(&value, fetched_at).await {
+            warn!("Failed to persist {} cache: {:#}", cache.name, err);
+          }
         }
       }
+      .instrument(span),
+    );
+  }
+
+  fn record_lookup(&self, result: &'static str) {
+    counter!("cache_requests_total", "cache" => self.name.clone(), "result" => result).increment(1);
+  }
+
+  fn last_error(&self, state: &CacheState<U::Output>) -> anyhow::Error {
+    match &state.last_error {
+      Some(err) => anyhow!("Failed to refresh {} cache: {}", self.name, err),
+      None => anyhow!("{} cache is empty", self.name),
     }
+  }
+}
+
+impl<U> Cache<U>
+where
+  U: Updater + Send + Sync + 'static,
+  U::Output: Serialize + DeserializeOwned + Send + Sync,
+{
+  /// Persists every refreshed value to the state directory, if one is
+  /// configured, and restores the last persisted value. Restored values are
+  /// flagged as stale until the first successful refresh.
+  pub(crate) fn persist_in(mut self, state_directory: Option<&Path>) -> Self {
+    let Some(state_directory) = state_directory else {
+      return self;
+    };
+
+    let state_file = StateFile {
+      path: state_directory.join(format!("{}.json", self.name.replace(' ', "_"))),
+      encode: |value, fetched_at| serde_json::to_vec(&Persisted { fetched_at, value }),
+    };
 
-    let new = match self.updater.update().await {
-      Ok(new) => {
-        let new = Arc::new(new);
-        *self.cached.write().await = Some((now + MAX_AGE, new.clone()));
-        Ok(new)
+    match state_file.load() {
+      Ok(Some(entry)) => {
+        info!(
+          "Restored {} cache from {}",
+          self.name,
+          state_file.path.display()
+        );
+        self.state.get_mut().unwrap().entry = Some(entry);
       }
-      Err(err) => Err(err),
+      Ok(None) => {}
+      Err(err) => warn!("Failed to restore {} cache: {:#}", self.name, err),
+    }
+
+    self.state_file = Some(state_file);
+    self
+  }
+}
+
+impl<T> StateFile<T> {
+  fn load(&self) -> anyhow::Result<Option<Entry<T>>>
+  where
+    T: DeserializeOwned,
+  {
+    let content = match std::fs::read(&self.path) {
+      Ok(content) => content,
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+      Err(err) => return Err(err.into()),
     };
+    let persisted: Persisted<T> = serde_json::from_slice(&content)?;
 
-    self.updating.store(false, Ordering::Relaxed);
+    let age = (OffsetDateTime::now_utc() - persisted.fetched_at).unsigned_abs();
+    let now = Instant::now();
+
+    Ok(Some(Entry {
+      value: Arc::new(persisted.value),
+      fetched_at: now.checked_sub(age).unwrap_or(now),
+      updated_at: persisted.fetched_at,
+      restored: true,
+    }))
+  }
+
+  /// Persists `value` with the time it was fetched, so that its age is kept
+  /// across restarts.
+  async fn save(&self, value: &T, fetched_at: OffsetDateTime) -> anyhow::Result<()> {
+    let content = (self.encode)(value, fetched_at)?;
+
+    if let Some(parent) = self.path.parent() {
+      tokio::fs::create_dir_all(parent).await?;
+    }
 
-    new
+    // write to a temporary file first, so a crash never leaves a partial file
+    let temporary = self.path.with_extension("json.tmp");
+    tokio::fs::write(&temporary, content).await?;
+    tokio::fs::rename(&temporary, &self.path).await?;
+    Ok(())
   }
 }
@@ -0,0 +1,59 @@
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+use siphasher::sip::SipHasher13;
+use time::OffsetDateTime;
+
+/// Weak entity tag derived from a hash of the content it describes. It is
+/// weak as the same tag is sent for the compressed and uncompressed
+/// representations, which are equivalent but not byte-identical.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ETag(u64);
+
+impl ETag {
+  pub(crate) fn of(value: impl Hash) -> Self {
+    // unlike the std `DefaultHasher` the algorithm is fixed, so tags survive
+    // toolchain updates
+    let mut hasher = SipHasher13::new_with_keys(0, 0);
+    value.hash(&mut hasher);
+    Self(hasher.finish())
+  }
+
+  /// Tag of a response built from several parts, changing whenever one of the
+  /// parts or their order changes.
+  pub(crate) fn combine(tags: impl IntoIterator<Item = ETag>) -> Self {
+    Self::of(tags.into_iter().collect::<Vec<_>>())
+  }
+}
+
+impl Display for ETag {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "W/\"{:016x}\"", self.0)
+  }
+}
+
+/// Validators of a response, used to answer conditional requests.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Validators {
+  pub(crate) etag: ETag,
+  pub(crate) last_modified: Option<OffsetDateTime>,
+}
+
+/// A response value together with its validators.
+pub(crate) struct Conditional<T> {
+  pub(crate) validators: Validators,
+  pub(crate) value: T,
+}
+
+impl<T> Conditional<T> {
+  pub(crate) fn new(validators: Validators, value: T) -> Self {
+    Self { validators, value }
+  }
+
+  pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> Conditional<U> {
+    Conditional {
+      validators: self.validators,
+      value: f(self.value),
+    }
+  }
+}
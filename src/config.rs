@@ -0,0 +1,355 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::args::Args;
+
+/// Effective configuration, the defaults overridden by the config file, which
+/// is in turn overridden by environment variables and flags.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+  pub(crate) listen_addr: SocketAddr,
+  /// Separate address `/metrics` is served on instead of `listen_addr`.
+  pub(crate) metrics_listen_addr: Option<SocketAddr>,
+  pub(crate) content_directory: PathBuf,
+  pub(crate) base_url: Url,
+  pub(crate) state_directory: Option<PathBuf>,
+  pub(crate) languages: Vec<String>,
+  pub(crate) fallback_languages: Vec<String>,
+  pub(crate) preview_token: Option<String>,
+  /// Networks of reverse proxies trusted to name the client in the
+  /// forwarded header.
+  pub(crate) trusted_proxies: Vec<IpNet>,
+  /// Header the trusted proxies append the client to, any other forwarding
+  /// header is ignored as the client may have sent it.
+  pub(crate) forwarded_header: ForwardedHeader,
+  /// Timeout of every request to an upstream source in seconds.
+  #[serde(with = "seconds")]
+  pub(crate) upstream_timeout: Duration,
+  pub(crate) log: LogConfig,
+  pub(crate) http_cache: HttpCacheConfig,
+  pub(crate) cors: CorsConfig,
+  pub(crate) stats: StatsConfig,
+  pub(crate) peers: PeersConfig,
+  pub(crate) looking_glass: LookingGlassConfig,
+  pub(crate) bird: BirdConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ForwardedHeader {
+  /// `X-Forwarded-For`, e.g. as set by nginx' `recommendedProxySettings`.
+  XForwardedFor,
+  /// `Forwarded` as specified by RFC 7239.
+  Forwarded,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LogConfig {
+  pub(crate) format: LogFormat,
+  /// Log levels in the `RUST_LOG` syntax, e.g.
+  /// `info,website_content_api::looking_glass=debug`.
+  pub(crate) filter: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LogFormat {
+  Compact,
+  Json,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct HttpCacheConfig {
+  #[serde(with = "seconds")]
+  pub(crate) assets_max_age: Duration,
+  #[serde(with = "seconds")]
+  pub(crate) content_max_age: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct CorsConfig {
+  /// Origins allowed to call the api, e.g. `https://dd-ix.net`, or
+  /// `https://*.dd-ix.net` for every subdomain. `*` allows every origin and
+  /// has to be configured explicitly.
+  pub(crate) origins: Vec<String>,
+  /// Request headers allowed in addition to the safelisted ones.
+  pub(crate) headers: Vec<String>,
+  /// Allow cookies and the `Authorization` header, requires explicit origins.
+  pub(crate) credentials: bool,
+  /// Seconds browsers may cache the answer to a preflight request.
+  #[serde(with = "seconds")]
+  pub(crate) max_age: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct StatsConfig {
+  pub(crate) prometheus_url: Url,
+  #[serde(with = "seconds")]
+  pub(crate) refresh_interval: Duration,
+  pub(crate) traffic_query: String,
+  pub(crate) as112_query: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PeersConfig {
+  pub(crate) ixp_manager_url: Url,
+  #[serde(with = "seconds")]
+  pub(crate) refresh_interval: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LookingGlassConfig {
+  pub(crate) url: Url,
+  #[serde(with = "seconds")]
+  pub(crate) refresh_interval: Duration,
+  /// Route servers whose neighbors and received routes are crawled, every
+  /// route server of the looking glass if empty.
+  pub(crate) route_servers: Vec<String>,
+  /// Neighbors whose received routes are fetched at the same time.
+  pub(crate) concurrency: usize,
+  /// Upper bound of requests to the looking glass, 0 disables the limit.
+  pub(crate) requests_per_second: u32,
+  /// Timeout of a single request to the looking glass in seconds.
+  #[serde(with = "seconds")]
+  pub(crate) request_timeout: Duration,
+  /// Attempts after a timeout, connection or server error.
+  pub(crate) retries: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BirdConfig {
+  pub(crate) html: PathBuf,
+  #[serde(with = "seconds")]
+  pub(crate) refresh_interval: Duration,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      listen_addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
+      metrics_listen_addr: None,
+      content_directory: PathBuf::from("content"),
+      base_url: Url::parse("http://localhost:8080/").unwrap(),
+      state_directory: None,
+      languages: vec!["en".to_string(), "de".to_string()],
+      fallback_languages: Vec::new(),
+      preview_token: None,
+      trusted_proxies: vec!["127.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()],
+      forwarded_header: ForwardedHeader::XForwardedFor,
+      upstream_timeout: Duration::from_secs(30),
+      log: LogConfig::default(),
+      http_cache: HttpCacheConfig::default(),
+      cors: CorsConfig::default(),
+      stats: StatsConfig::default(),
+      peers: PeersConfig::default(),
+      looking_glass: LookingGlassConfig::default(),
+      bird: BirdConfig::default(),
+    }
+  }
+}
+
+impl Default for LogConfig {
+  fn default() -> Self {
+    Self {
+      format: LogFormat::Compact,
+      filter: "info".to_string(),
+    }
+  }
+}
+
+impl Default for HttpCacheConfig {
+  fn default() -> Self {
+    Self {
+      assets_max_age: Duration::from_secs(60 * 60 * 24 * 7),
+      content_max_age: Duration::from_secs(60),
+    }
+  }
+}
+
+impl Default for CorsConfig {
+  fn default() -> Self {
+    Self {
+      origins: vec![
+        "https://dd-ix.net".to_string(),
+        "https://*.dd-ix.net".to_string(),
+      ],
+      headers: vec!["content-type".to_string(), "authorization".to_string()],
+      credentials: false,
+      max_age: Duration::from_secs(60 * 60),
+    }
+  }
+}
+
+impl Default for StatsConfig {
+  fn default() -> Self {
+    Self {
+      prometheus_url: Url::parse("https://svc-prom01.dd-ix.net/").unwrap(),
+      refresh_interval: Duration::from_secs(60 * 10),
+      traffic_query: "sum(rate(sflow_router_bytes[5m]))*8".to_string(),
+      as112_query: "sum by (type) (rate(knot_query_type_total[5m]) or rate(knot_stats_query_type_total[5m])) >= 0.01".to_string(),
+    }
+  }
+}
+
+impl Default for PeersConfig {
+  fn default() -> Self {
+    Self {
+      ixp_manager_url: Url::parse("https://portal.dd-ix.net/").unwrap(),
+      refresh_interval: Duration::from_secs(60 * 10),
+    }
+  }
+}
+
+impl Default for LookingGlassConfig {
+  fn default() -> Self {
+    Self {
+      url: Url::parse("https://lg.dd-ix.net/").unwrap(),
+      refresh_interval: Duration::from_secs(60 * 60),
+      route_servers: Vec::new(),
+      concurrency: 8,
+      requests_per_second: 20,
+      request_timeout: Duration::from_secs(30),
+      retries: 3,
+    }
+  }
+}
+
+impl Default for BirdConfig {
+  fn default() -> Self {
+    Self {
+      html: PathBuf::from("/var/lib/website-content-api/bird.html"),
+      refresh_interval: Duration::from_secs(60 * 10),
+    }
+  }
+}
+
+impl Config {
+  /// Reads the config file given by `--config`, if any, and applies the
+  /// environment variables and flags on top of it.
+  pub(crate) fn load(args: Args) -> anyhow::Result<Self> {
+    let mut config = match &args.config {
+      Some(path) => Self::read(path)?,
+      None => Self::default(),
+    };
+
+    let Args {
+      command: _,
+      config: _,
+      listen_addr,
+      metrics_listen_addr,
+      content_directory,
+      base_url,
+      prometheus_url,
+      ixp_manager_url,
+      looking_glass_url,
+      bird_html,
+      languages,
+      state_directory,
+      fallback_languages,
+      preview_token,
+      trusted_proxies,
+      forwarded_header,
+      cors_origins,
+      assets_max_age,
+      content_max_age,
+      log_format,
+      log_filter,
+    } = args;
+
+    override_with(&mut config.listen_addr, listen_addr);
+    override_with(&mut config.content_directory, content_directory);
+    override_with(&mut config.base_url, base_url);
+    override_with(&mut config.stats.prometheus_url, prometheus_url);
+    override_with(&mut config.peers.ixp_manager_url, ixp_manager_url);
+    override_with(&mut config.looking_glass.url, looking_glass_url);
+    override_with(&mut config.bird.html, bird_html);
+    override_with(&mut config.languages, languages);
+    override_with(&mut config.fallback_languages, fallback_languages);
+    override_with(&mut config.trusted_proxies, trusted_proxies);
+    override_with(&mut config.forwarded_header, forwarded_header);
+    override_with(&mut config.cors.origins, cors_origins);
+    override_with(&mut config.log.format, log_format);
+    override_with(&mut config.log.filter, log_filter);
+    override_with(
+      &mut config.http_cache.assets_max_age,
+      assets_max_age.map(Duration::from_secs),
+    );
+    override_with(
+      &mut config.http_cache.content_max_age,
+      content_max_age.map(Duration::from_secs),
+    );
+    if metrics_listen_addr.is_some() {
+      config.metrics_listen_addr = metrics_listen_addr;
+    }
+    if state_directory.is_some() {
+      config.state_directory = state_directory;
+    }
+    if preview_token.is_some() {
+      config.preview_token = preview_token;
+    }
+
+    Ok(config)
+  }
+
+  fn read(path: &Path) -> anyhow::Result<Self> {
+    let content = std::fs::read_to_string(path)
+      .map_err(|err| anyhow!("Cannot read config file {}: {}", path.display(), err))?;
+
+    let config = match path.extension().and_then(|extension| extension.to_str()) {
+      Some("toml") => toml::from_str(&content).map_err(|err| anyhow!("{}", err)),
+      Some("yaml" | "yml") => serde_yaml_ng::from_str(&content).map_err(|err| anyhow!("{}", err)),
+      _ => Err(anyhow!("Expected a .toml, .yaml or .yml file")),
+    };
+
+    config.map_err(|err| anyhow!("Invalid config file {}: {}", path.display(), err))
+  }
+
+  /// Renders the configuration as TOML, hiding secrets.
+  pub(crate) fn into_toml(mut self) -> anyhow::Result<String> {
+    if self.preview_token.is_some() {
+      self.preview_token = Some("<redacted>".to_string());
+    }
+    Ok(toml::to_string(&self)?)
+  }
+}
+
+fn override_with<T>(value: &mut T, flag: Option<T>) {
+  if let Some(flag) = flag {
+    *value = flag;
+  }
+}
+
+/// Durations as whole seconds.
+mod seconds {
+  use std::time::Duration;
+
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub(super) fn serialize<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+  }
+
+  pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_secs)
+  }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 
-use crate::lang::Language;
+use crate::lang::{Language, Localized};
+use crate::validate::{read_yaml, ContentError};
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct Document {
@@ -14,19 +16,62 @@ pub(crate) struct Document {
   image: String,
 }
 
+type DocumentMap = HashMap<Language, Arc<Vec<Document>>>;
+
 #[derive(Clone)]
 pub(crate) struct Documents {
-  documents: Arc<HashMap<Language, Arc<Vec<Document>>>>,
+  path: Arc<PathBuf>,
+  documents: Arc<RwLock<Arc<DocumentMap>>>,
 }
 
 impl Documents {
   pub(crate) async fn load(path: &Path) -> anyhow::Result<Self> {
-    let string = tokio::fs::read_to_string(path.join("documents.yaml")).await?;
-    let documents = serde_yaml_ng::from_str(&string)?;
-    Ok(Self { documents })
+    let documents = read_documents(path).await?;
+    Ok(Self {
+      path: Arc::new(path.to_path_buf()),
+      documents: Arc::new(RwLock::new(documents)),
+    })
+  }
+
+  pub(crate) async fn reload(&self) -> anyhow::Result<()> {
+    let documents = read_documents(&self.path).await?;
+    *self.documents.write().await = documents;
+    Ok(())
+  }
+
+  /// Checks the documents file and that there are documents in every
+  /// language.
+  pub(crate) async fn validate(path: &Path) -> Vec<ContentError> {
+    let path = path.join("documents.yaml");
+    let documents: DocumentMap = match read_yaml(&path).await {
+      Ok(documents) => documents,
+      Err(err) => return vec![err],
+    };
+
+    Language::all()
+      .iter()
+      .copied()
+      .filter(|lang| !documents.contains_key(lang))
+      .map(|lang| ContentError::new(&path, None, format!("No {} documents", lang.code())))
+      .collect()
   }
 
-  pub(crate) fn documents(&self, lang: Language) -> Option<Arc<Vec<Document>>> {
-    self.documents.get(&lang).cloned()
+  /// Returns the documents of the first language of `langs` there are
+  /// documents in.
+  pub(crate) async fn documents(
+    &self,
+    langs: &[Language],
+  ) -> Option<Localized<Arc<Vec<Document>>>> {
+    let documents = self.documents.read().await;
+    langs.iter().find_map(|lang| {
+      documents
+        .get(lang)
+        .map(|documents| Localized::new(*lang, documents.clone()))
+    })
   }
 }
+
+async fn read_documents(path: &Path) -> Result<Arc<DocumentMap>, ContentError> {
+  let documents: DocumentMap = read_yaml(&path.join("documents.yaml")).await?;
+  Ok(Arc::new(documents))
+}
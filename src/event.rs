@@ -1,11 +1,17 @@
 use std::sync::Arc;
 
 use crate::lang::Language;
+use anyhow::anyhow;
+use ics::properties::{Categories, Description, DtEnd, DtStart, Location, Summary, URL};
+use ics::{escape_text, ICalendar};
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{OffsetDateTime, UtcOffset};
 use url::Url;
 
-use crate::posts::post_provider::{LongPostFromMeta, PostMeta, PostProvider};
+use crate::posts::post_provider::{
+  LongPostFromMeta, PostContent, PostMeta, PostProvider, Translatable,
+};
+use crate::posts::{Alternates, Publication};
 
 pub(crate) type Events = PostProvider<EventMeta, SmallEventPost, EventPost>;
 
@@ -21,6 +27,9 @@ pub(crate) struct EventMeta {
   keywords: Vec<String>,
   image: Option<String>,
   link: Option<Url>,
+  #[serde(flatten)]
+  publication: Publication,
+  translation_key: Option<String>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -38,6 +47,11 @@ pub(crate) struct EventPost {
   keywords: Vec<String>,
   image: Option<String>,
   link: Option<Url>,
+  #[serde(flatten)]
+  publication: Publication,
+  #[serde(skip)]
+  translation_key: Option<String>,
+  alternates: Alternates,
   body: String,
 }
 
@@ -55,6 +69,8 @@ pub(crate) struct SmallEventPost {
   description: String,
   keywords: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
 }
 
 impl LongPostFromMeta<EventMeta> for EventPost {
@@ -71,6 +87,9 @@ impl LongPostFromMeta<EventMeta> for EventPost {
       keywords: meta.keywords,
       image: meta.image,
       link: meta.link,
+      publication: meta.publication,
+      translation_key: meta.translation_key,
+      alternates: Alternates::new(),
       body,
     }
   }
@@ -89,6 +108,7 @@ impl From<Arc<EventPost>> for SmallEventPost {
       description: event.description.clone(),
       keywords: event.keywords.clone(),
       image: event.image.clone(),
+      publication: event.publication,
     }
   }
 }
@@ -109,6 +129,18 @@ impl PostMeta for SmallEventPost {
   fn keywords(&self) -> &Vec<String> {
     &self.keywords
   }
+
+  fn publication(&self) -> Publication {
+    self.publication
+  }
+
+  fn date(&self) -> OffsetDateTime {
+    self.start_time
+  }
+
+  fn last_modified(&self) -> Option<OffsetDateTime> {
+    None
+  }
 }
 
 impl PostMeta for EventPost {
@@ -127,4 +159,101 @@ impl PostMeta for EventPost {
   fn keywords(&self) -> &Vec<String> {
     &self.keywords
   }
+
+  fn publication(&self) -> Publication {
+    self.publication
+  }
+
+  fn date(&self) -> OffsetDateTime {
+    self.start_time
+  }
+
+  fn last_modified(&self) -> Option<OffsetDateTime> {
+    None
+  }
+}
+
+impl Translatable for EventPost {
+  fn explicit_translation_key(&self) -> Option<&str> {
+    self.translation_key.as_deref()
+  }
+
+  fn alternates(&self) -> &Alternates {
+    &self.alternates
+  }
+
+  fn set_alternates(&mut self, alternates: Alternates) {
+    self.alternates = alternates;
+  }
+}
+
+impl PostContent for EventPost {
+  fn title(&self) -> &str {
+    &self.title
+  }
+
+  fn description(&self) -> &str {
+    &self.description
+  }
+
+  fn body(&self) -> &str {
+    &self.body
+  }
+}
+
+/// Renders the given events as an RFC 5545 iCalendar document.
+pub(crate) fn calendar(events: &[Arc<EventPost>], base_url: &Url) -> anyhow::Result<String> {
+  let host = base_url
+    .host_str()
+    .ok_or_else(|| anyhow!("Base url {} has no host", base_url))?;
+  let dtstamp = ical_date_time(OffsetDateTime::now_utc());
+
+  let mut calendar = ICalendar::new("2.0", "-//DD-IX//website-content-api//EN");
+  calendar.push(ics::components::Property::new(
+    "X-WR-CALNAME",
+    "DD-IX Events",
+  ));
+
+  for event in events {
+    let mut ical_event = ics::Event::new(
+      format!("{}.{}@{}", event.slug, event.lang.code(), host),
+      dtstamp.clone(),
+    );
+    ical_event.push(Summary::new(escape_text(event.title.as_str())));
+    ical_event.push(DtStart::new(ical_date_time(event.start_time)));
+    ical_event.push(DtEnd::new(ical_date_time(event.end_time)));
+    ical_event.push(Location::new(escape_text(event.location.as_str())));
+    ical_event.push(Description::new(escape_text(event.description.as_str())));
+    if !event.keywords.is_empty() {
+      ical_event.push(Categories::new(
+        event
+          .keywords
+          .iter()
+          .map(|keyword| escape_text(keyword.as_str()))
+          .collect::<Vec<_>>()
+          .join(","),
+      ));
+    }
+    if let Some(link) = &event.link {
+      ical_event.push(URL::new(link.to_string()));
+    }
+
+    calendar.add_event(ical_event);
+  }
+
+  Ok(calendar.to_string())
+}
+
+fn ical_date_time(date_time: OffsetDateTime) -> String {
+  let date_time = date_time.to_offset(UtcOffset::UTC);
+
+  format!(
+    "{:0>4}{:0>2}{:0>2}T{:0>2}{:0>2}{:0>2}Z",
+    date_time.year(),
+    date_time.month() as u8,
+    date_time.day(),
+    date_time.hour(),
+    date_time.minute(),
+    date_time.second()
+  )
 }
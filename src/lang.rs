@@ -1,22 +1,168 @@
+use std::sync::OnceLock;
+
 use anyhow::anyhow;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Languages content can be written in, configured once at startup.
+static LANGUAGES: OnceLock<Vec<Language>> = OnceLock::new();
+
+/// One of the configured languages, identified by its language code, e.g.
+/// `en` or `cs`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Language(&'static str);
+
+/// Sets the languages content can be written in. Has to be called before any
+/// content is loaded and only once.
+pub(crate) fn init(codes: &[String]) -> anyhow::Result<()> {
+  if codes.is_empty() {
+    return Err(anyhow!("At least one language has to be configured"));
+  }
+
+  let mut languages: Vec<Language> = Vec::with_capacity(codes.len());
+  for code in codes {
+    let code = code.trim();
+    if code.is_empty()
+      || !code
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+      return Err(anyhow!("Invalid lang code {}", code));
+    }
+    if languages.iter().any(|lang| lang.0 == code) {
+      return Err(anyhow!("Lang code {} is configured twice", code));
+    }
+
+    languages.push(Language(Box::leak(code.to_string().into_boxed_str())));
+  }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) enum Language {
-  #[serde(rename = "en")]
-  English,
-  #[serde(rename = "de")]
-  German,
+  LANGUAGES
+    .set(languages)
+    .map_err(|_| anyhow!("Languages are already configured"))
 }
 
 impl TryFrom<&str> for Language {
   type Error = anyhow::Error;
 
   fn try_from(value: &str) -> Result<Self, Self::Error> {
-    match value {
-      "en" => Ok(Language::English),
-      "de" => Ok(Language::German),
-      _ => Err(anyhow!("Invalid lang code {}", value)),
+    Language::all()
+      .iter()
+      .find(|lang| lang.0 == value)
+      .copied()
+      .ok_or_else(|| anyhow!("Unsupported lang code {}", value))
+  }
+}
+
+impl Language {
+  /// All configured languages in the configured order.
+  pub(crate) fn all() -> &'static [Language] {
+    LANGUAGES.get().map_or(&[], Vec::as_slice)
+  }
+
+  pub(crate) fn code(&self) -> &'static str {
+    self.0
+  }
+}
+
+impl Serialize for Language {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.0)
+  }
+}
+
+impl<'de> Deserialize<'de> for Language {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let code = String::deserialize(deserializer)?;
+    Language::try_from(code.as_str()).map_err(serde::de::Error::custom)
+  }
+}
+
+/// Content together with the languages it is actually served in, which can
+/// differ from the requested one because of the fallback order.
+pub(crate) struct Localized<T> {
+  pub(crate) langs: Vec<Language>,
+  pub(crate) content: T,
+}
+
+impl<T> Localized<T> {
+  pub(crate) fn new(lang: Language, content: T) -> Self {
+    Self {
+      langs: vec![lang],
+      content,
+    }
+  }
+
+  pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> Localized<U> {
+    Localized {
+      langs: self.langs,
+      content: f(self.content),
+    }
+  }
+}
+
+/// Order in which languages are tried when a client does not ask for a
+/// specific one or content is missing in the requested language.
+#[derive(Debug)]
+pub(crate) struct Languages {
+  fallback: Vec<Language>,
+}
+
+impl Languages {
+  /// Falls back to the configured languages in their configured order if no
+  /// explicit fallback order is given.
+  pub(crate) fn new(fallback: Vec<Language>) -> Self {
+    if fallback.is_empty() {
+      return Self {
+        fallback: Language::all().to_vec(),
+      };
     }
+
+    Self { fallback }
+  }
+
+  /// The language used when the client does not accept any known language.
+  pub(crate) fn default_lang(&self) -> Language {
+    self.fallback[0]
+  }
+
+  /// The requested language followed by the remaining fallback languages.
+  pub(crate) fn chain(&self, lang: Language) -> Vec<Language> {
+    std::iter::once(lang)
+      .chain(self.fallback.iter().copied().filter(|other| *other != lang))
+      .collect()
+  }
+
+  /// Picks the language with the highest quality from an `Accept-Language`
+  /// header, e.g. `de-DE,de;q=0.9,en;q=0.8`.
+  pub(crate) fn negotiate(&self, accept_language: Option<&str>) -> Language {
+    let mut best: Option<(f32, Language)> = None;
+
+    for range in accept_language.unwrap_or_default().split(',') {
+      let mut parts = range.split(';').map(str::trim);
+      let tag = parts.next().unwrap_or_default();
+      let quality = parts
+        .find_map(|param| param.strip_prefix("q="))
+        .and_then(|quality| quality.parse::<f32>().ok())
+        .unwrap_or(1.0);
+
+      let lang = if tag == "*" {
+        Some(self.default_lang())
+      } else {
+        // configured codes may have a region, e.g. `pt-br`, so the whole tag
+        // is preferred over its primary subtag
+        let tag = tag.to_lowercase();
+        let primary = tag.split('-').next().unwrap_or_default();
+        Language::try_from(tag.as_str())
+          .or_else(|_| Language::try_from(primary))
+          .ok()
+      };
+
+      if let Some(lang) = lang {
+        if quality > 0.0 && best.is_none_or(|(best, _)| quality > best) {
+          best = Some((quality, lang));
+        }
+      }
+    }
+
+    best.map_or_else(|| self.default_lang(), |(_, lang)| lang)
   }
 }
@@ -1,20 +1,154 @@
-use crate::cache::{Cache, Updater};
-use ipnet::IpNet;
-use reqwest::Client;
-use serde::Deserialize;
+use crate::cache::{Cache, RefreshPolicy, Updater};
+use crate::config::LookingGlassConfig;
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use prefix_trie::PrefixMap;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
+use std::path::Path;
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{Interval, MissedTickBehavior};
+use tracing::{debug, debug_span, info, info_span, warn, Instrument};
 use url::Url;
 
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Neighbors between two progress log lines.
+const PROGRESS_INTERVAL: usize = 50;
+
 #[derive(Clone)]
 pub(crate) struct LookingGlassUpdater {
   looking_glass_url: Url,
+  route_servers: Vec<String>,
   client: Client,
+  limits: CrawlLimits,
+}
+
+/// Limits of the crawl, which sends a request per page of received routes of
+/// every neighbor.
+#[derive(Clone)]
+struct CrawlLimits {
+  neighbors: Arc<Semaphore>,
+  pacing: Option<Arc<Mutex<Interval>>>,
+  request_timeout: Duration,
+  retries: u32,
+}
+
+/// Networks received from a neighbor, possibly incomplete if fetching one of
+/// the pages failed.
+struct NeighborRoutes {
+  id: String,
+  asn: i32,
+  networks: Vec<IpNet>,
+  error: Option<anyhow::Error>,
+}
+
+/// Neighbor announcing a network to a route server.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct Announcement {
+  pub(crate) neighbor_asn: i32,
+  pub(crate) route_server: Arc<str>,
+}
+
+/// Received routes in one radix trie per address family, built once per
+/// refresh so lookups do not have to scan a full table.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct RouteTable {
+  v4: PrefixMap<Ipv4Net, Vec<Announcement>>,
+  v6: PrefixMap<Ipv6Net, Vec<Announcement>>,
+}
+
+impl RouteTable {
+  fn insert(&mut self, network: IpNet, announcement: Announcement) {
+    let announcements = match network.trunc() {
+      IpNet::V4(network) => self.v4.entry(network).or_default(),
+      IpNet::V6(network) => self.v6.entry(network).or_default(),
+    };
+    // the same route is received in both sessions and may span pages
+    if !announcements.contains(&announcement) {
+      announcements.push(announcement);
+    }
+  }
+
+  /// Number of distinct networks.
+  pub(crate) fn len(&self) -> usize {
+    self.v4.len() + self.v6.len()
+  }
+
+  /// Most specific network containing `addr`.
+  pub(crate) fn longest_match(&self, addr: IpAddr) -> Option<(IpNet, &[Announcement])> {
+    match IpNet::from(addr) {
+      IpNet::V4(addr) => self
+        .v4
+        .get_lpm(&addr)
+        .map(|(network, announcements)| (network.into(), announcements.as_slice())),
+      IpNet::V6(addr) => self
+        .v6
+        .get_lpm(&addr)
+        .map(|(network, announcements)| (network.into(), announcements.as_slice())),
+    }
+  }
+
+  /// Networks containing `prefix`, including `prefix` itself, most specific
+  /// first.
+  pub(crate) fn covering(&self, prefix: IpNet) -> Vec<(IpNet, &[Announcement])> {
+    let mut covering: Vec<(IpNet, &[Announcement])> = match prefix.trunc() {
+      IpNet::V4(prefix) => self
+        .v4
+        .cover(&prefix)
+        .map(|(network, announcements)| (network.into(), announcements.as_slice()))
+        .collect(),
+      IpNet::V6(prefix) => self
+        .v6
+        .cover(&prefix)
+        .map(|(network, announcements)| (network.into(), announcements.as_slice()))
+        .collect(),
+    };
+    covering.reverse();
+    covering
+  }
+
+  /// Networks more specific than `prefix`, in lexicographic order.
+  pub(crate) fn covered(&self, prefix: IpNet) -> Vec<(IpNet, &[Announcement])> {
+    let prefix = prefix.trunc();
+    let children: Vec<(IpNet, &[Announcement])> = match prefix {
+      IpNet::V4(prefix) => self
+        .v4
+        .children(&prefix)
+        .map(|(network, announcements)| (network.into(), announcements.as_slice()))
+        .collect(),
+      IpNet::V6(prefix) => self
+        .v6
+        .children(&prefix)
+        .map(|(network, announcements)| (network.into(), announcements.as_slice()))
+        .collect(),
+    };
+    children
+      .into_iter()
+      .filter(|(network, _)| *network != prefix)
+      .collect()
+  }
+}
+
+#[derive(Deserialize)]
+struct LookingGlassRouteServer {
+  id: String,
+}
+
+#[derive(Deserialize)]
+struct LookingGlassRouteServers {
+  routeservers: Vec<LookingGlassRouteServer>,
 }
 
+/// Session of a neighbor with a route server, e.g. `AS64500_1`, a neighbor
+/// may have a session per address family.
 #[derive(Deserialize)]
 pub(crate) struct LookingGlassNeighbor {
+  id: String,
   asn: i32,
 }
 
@@ -23,91 +157,229 @@ pub(crate) struct LookingGlassNeighbors {
   neighbors: Vec<LookingGlassNeighbor>,
 }
 
-impl LookingGlassUpdater {
-  pub(crate) async fn load(looking_glass_url: Url) -> anyhow::Result<Self> {
-    Ok(Self {
-      looking_glass_url,
-      client: Default::default(),
-    })
-  }
-}
-
 impl Updater for LookingGlassUpdater {
-  type Output = Vec<IpNet>;
+  type Output = RouteTable;
   type Error = anyhow::Error;
 
   async fn update(&self) -> Result<Self::Output, Self::Error> {
     info!("Updating looking glass cache!");
-    let asns: Vec<i32> = self
-      .client
-      .get(
+    let mut routes = RouteTable::default();
+    let mut last_error = None;
+    let mut crawled = 0;
+
+    let route_servers = if self.route_servers.is_empty() {
+      let route_servers = self.discover_route_servers().await?;
+      info!(?route_servers, "Discovered route servers");
+      route_servers
+    } else {
+      self.route_servers.clone()
+    };
+
+    // a failing route server only loses its own routes
+    for route_server in &route_servers {
+      let span = info_span!("crawl", route_server = %route_server);
+      match self.crawl(route_server, &mut routes).instrument(span).await {
+        Ok(()) => crawled += 1,
+        Err(err) => {
+          warn!("Cannot crawl route server {}: {:#}", route_server, err);
+          last_error = Some(err);
+        }
+      }
+    }
+
+    match last_error {
+      Some(err) if crawled == 0 => Err(err),
+      _ => Ok(routes),
+    }
+  }
+}
+
+impl LookingGlassUpdater {
+  async fn discover_route_servers(&self) -> anyhow::Result<Vec<String>> {
+    let route_servers: Vec<String> = self
+      .fetch::<LookingGlassRouteServers>(self.looking_glass_url.join("/api/v1/routeservers")?)
+      .await?
+      .routeservers
+      .into_iter()
+      .map(|route_server| route_server.id)
+      .collect();
+
+    if route_servers.is_empty() {
+      return Err(anyhow::anyhow!("The looking glass has no route servers"));
+    }
+    Ok(route_servers)
+  }
+
+  async fn crawl(&self, route_server: &str, routes: &mut RouteTable) -> anyhow::Result<()> {
+    let sessions = self
+      .fetch::<LookingGlassNeighbors>(
         self
           .looking_glass_url
-          .join("/api/v1/routeservers/rs01_v4/neighbors")?,
+          .join(&format!("/api/v1/routeservers/{}/neighbors", route_server))?,
       )
-      .send()
-      .await?
-      .error_for_status()?
-      .json::<LookingGlassNeighbors>()
       .await?
-      .neighbors
-      .iter()
-      .map(|neighbor| neighbor.asn)
-      .collect();
+      .neighbors;
+
+    let total = sessions.len();
+    info!(neighbors = total, "Fetching received routes");
 
-    println!("Fetching routes from {} asns!", asns.len());
-    let mut routes = Vec::new();
-
-    for inet_type in ["v4", "v6"] {
-      for session_type in [1, 2] {
-        for asn in &asns {
-          let mut total_number_of_pages = 1;
-          let mut current_page = 0;
-          while current_page < total_number_of_pages {
-            println!(
-              "fetching {inet_type} routes for asn {asn}: page {current_page}/{total_number_of_pages}",
-            );
-            println!("{}", self.looking_glass_url.join(&format!(
-              "/api/v1/routeservers/rs01_{}/neighbors/AS{}_{}/routes/received?page={}",
-              inet_type, asn, session_type, current_page,
-            ))?);
-            match self
-                .client
-                .get(self.looking_glass_url.join(&format!(
-                  "/api/v1/routeservers/rs01_{}/neighbors/AS{}_{}/routes/received?page={}",
-                  inet_type, asn, session_type, current_page,
-                ))?)
-                .send()
-                .await?
-                .error_for_status()
-            {
-              Ok(response) => {
-                println!("response from alice {}", &response.status());
-                let json_data = response.json::<LookingGlassRoutesScheme>().await?;
-                println!("response received: {} {}", json_data.imported.len(), json_data.pagination.total_pages);
-                total_number_of_pages = json_data.pagination.total_pages;
-                let mut route_array: Vec<IpNet> = json_data
-                    .imported
-                    .into_iter()
-                    .map(|looking_glass_import: LookingGlassImport| looking_glass_import.network)
-                    .collect();
-                routes.append(&mut route_array);
-              }
-              Err(e) => {
-                println!("error when fetching from looking glass: {e}");
-              }
-            }
-            current_page += 1;
-          }
+    let mut neighbors = JoinSet::new();
+    for session in sessions {
+      let updater = self.clone();
+      let route_server = route_server.to_string();
+      let span = debug_span!("neighbor", id = %session.id, asn = session.asn);
+      neighbors.spawn(
+        async move {
+          let _permit = updater
+            .limits
+            .neighbors
+            .acquire()
+            .await
+            .expect("the crawl semaphore is never closed");
+          updater.received_routes(&route_server, session).await
         }
+        .instrument(span),
+      );
+    }
+
+    let route_server_name: Arc<str> = Arc::from(route_server);
+    let mut done = 0;
+    let mut failed = 0;
+    while let Some(neighbor) = neighbors.join_next().await {
+      let neighbor = neighbor?;
+      done += 1;
+
+      if let Some(err) = &neighbor.error {
+        failed += 1;
+        warn!(
+          id = %neighbor.id,
+          asn = neighbor.asn,
+          "Cannot fetch received routes, keeping {} networks: {:#}",
+          neighbor.networks.len(),
+          err
+        );
+      }
+      for network in neighbor.networks {
+        routes.insert(
+          network,
+          Announcement {
+            neighbor_asn: neighbor.asn,
+            route_server: route_server_name.clone(),
+          },
+        );
+      }
+
+      if done % PROGRESS_INTERVAL == 0 {
+        info!(done, total, failed, "Fetching received routes");
       }
     }
-    IpNet::aggregate(&mut routes);
 
-    Ok(routes)
+    info!(neighbors = total, failed, "Fetched received routes");
+    if total > 0 && failed == total {
+      return Err(anyhow::anyhow!(
+        "Cannot fetch the received routes of any neighbor"
+      ));
+    }
+    Ok(())
+  }
+
+  async fn received_routes(
+    &self,
+    route_server: &str,
+    session: LookingGlassNeighbor,
+  ) -> NeighborRoutes {
+    let mut neighbor = NeighborRoutes {
+      id: session.id,
+      asn: session.asn,
+      networks: Vec::new(),
+      error: None,
+    };
+
+    let mut total_number_of_pages = 1;
+    let mut current_page = 0;
+    while current_page < total_number_of_pages {
+      let page = self
+        .looking_glass_url
+        .join(&format!(
+          "/api/v1/routeservers/{}/neighbors/{}/routes/received?page={}",
+          route_server, neighbor.id, current_page,
+        ))
+        .map_err(anyhow::Error::from);
+      debug!(
+        page = current_page,
+        pages = total_number_of_pages,
+        "Fetching received routes"
+      );
+
+      let json_data = match page {
+        Ok(url) => self.fetch::<LookingGlassRoutesScheme>(url).await,
+        Err(err) => Err(err),
+      };
+      match json_data {
+        Ok(json_data) => {
+          total_number_of_pages = json_data.pagination.total_pages;
+          neighbor.networks.extend(
+            json_data
+              .imported
+              .into_iter()
+              .map(|looking_glass_import| looking_glass_import.network),
+          );
+        }
+        Err(err) => {
+          neighbor.error = Some(err);
+          break;
+        }
+      }
+      current_page += 1;
+    }
+
+    neighbor
+  }
+
+  /// Fetches `url`, retrying transient failures with exponential backoff.
+  async fn fetch<T: DeserializeOwned>(&self, url: Url) -> anyhow::Result<T> {
+    let mut attempt = 0;
+    loop {
+      if let Some(pacing) = &self.limits.pacing {
+        pacing.lock().await.tick().await;
+      }
+
+      match self.try_fetch(url.clone()).await {
+        Ok(value) => return Ok(value),
+        Err(err) if attempt < self.limits.retries && is_transient(&err) => {
+          attempt += 1;
+          let backoff = RETRY_BACKOFF.saturating_mul(2u32.saturating_pow(attempt - 1));
+          debug!(attempt, "Retrying in {:?}: {}", backoff, err);
+          tokio::time::sleep(backoff).await;
+        }
+        Err(err) => return Err(err.into()),
+      }
+    }
+  }
+
+  async fn try_fetch<T: DeserializeOwned>(&self, url: Url) -> reqwest::Result<T> {
+    self
+      .client
+      .get(url)
+      .timeout(self.limits.request_timeout)
+      .send()
+      .await?
+      .error_for_status()?
+      .json::<T>()
+      .await
   }
 }
 
+fn is_transient(err: &reqwest::Error) -> bool {
+  err.is_timeout()
+    || err.is_connect()
+    || err.is_request()
+    || err.is_body()
+    || err
+      .status()
+      .is_some_and(|status| status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+}
+
 #[derive(Clone)]
 pub struct LookingGlass {
   pub routes: Arc<Cache<LookingGlassUpdater>>,
@@ -130,15 +402,32 @@ struct LookingGlassRoutesScheme {
 }
 
 impl LookingGlass {
-  pub(crate) async fn load(looking_glass_url: Url) -> anyhow::Result<Self> {
+  pub(crate) async fn load(
+    client: Client,
+    config: &LookingGlassConfig,
+    state_directory: Option<&Path>,
+  ) -> anyhow::Result<Self> {
+    let pacing = (config.requests_per_second > 0).then(|| {
+      let mut pacing = tokio::time::interval(Duration::from_secs(1) / config.requests_per_second);
+      pacing.set_missed_tick_behavior(MissedTickBehavior::Delay);
+      Arc::new(Mutex::new(pacing))
+    });
+
+    let updater = LookingGlassUpdater {
+      looking_glass_url: config.url.clone(),
+      route_servers: config.route_servers.clone(),
+      client,
+      limits: CrawlLimits {
+        neighbors: Arc::new(Semaphore::new(config.concurrency.max(1))),
+        pacing,
+        request_timeout: config.request_timeout,
+        retries: config.retries,
+      },
+    };
+    let refresh = RefreshPolicy::every(config.refresh_interval);
+
     Ok(Self {
-      routes: Arc::new(Cache::new(
-        LookingGlassUpdater::load(looking_glass_url).await?,
-      )),
+      routes: Arc::new(Cache::new("looking glass", refresh, updater).persist_in(state_directory)),
     })
   }
 }
-
-pub fn is_address_in_network(routes: &[IpNet], network: IpAddr) -> bool {
-  routes.iter().any(|x| x.contains(&network))
-}
@@ -1,32 +1,38 @@
-use axum::http::header::CONTENT_TYPE;
-use axum::http::Method;
+use axum::{middleware, ServiceExt};
 use clap::Parser;
-use std::time::Duration;
+use reqwest::Client;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{error, info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceBuilder;
+use tower_http::compression::CompressionLayer;
+use tracing::{error, info};
 
-use crate::args::Args;
+use crate::args::{Args, Command};
 use crate::bird::Bird;
 use crate::blog::Blogs;
+use crate::config::Config;
 use crate::documents::Documents;
 use crate::event::Events;
+use crate::lang::{Language, Languages};
 use crate::looking_glass::LookingGlass;
 use crate::mirrors::Mirrors;
 use crate::news::News;
 use crate::peers::NetworkService;
-use crate::routes::{route, ContentPaths};
+use crate::routes::{metrics_route, route, CachePolicy, ContentPaths, CorsPolicy, TrustedProxies};
 use crate::state::FoundationState;
 use crate::stats::Stats;
 use crate::team::Team;
 use crate::text_blocks::TextBlocks;
 
 mod args;
-mod auto_cache;
 mod bird;
 mod blog;
 mod cache;
+mod conditional;
+mod config;
 mod documents;
 mod event;
 mod lang;
@@ -38,19 +44,25 @@ mod posts;
 mod routes;
 mod state;
 mod stats;
+mod systemd;
 mod team;
+mod telemetry;
 mod text_blocks;
+mod validate;
+mod watcher;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-  let args = Args::parse();
+  let mut args = Args::parse();
+  let command = args.command.take();
+  let config = Config::load(args)?;
 
-  let subscriber = FmtSubscriber::builder()
-    .with_max_level(Level::INFO)
-    .compact()
-    .finish();
+  if let Some(Command::PrintConfig) = command {
+    print!("{}", config.into_toml()?);
+    return Ok(());
+  }
 
-  tracing::subscriber::set_global_default(subscriber)?;
+  telemetry::init_logging(&config.log)?;
 
   info!(concat!(
     "Booting ",
@@ -60,74 +72,162 @@ async fn main() -> anyhow::Result<()> {
     "..."
   ));
 
+  lang::init(&config.languages)?;
+  let fallback_languages = config
+    .fallback_languages
+    .iter()
+    .map(|code| Language::try_from(code.as_str()))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+
+  if let Some(Command::Validate) = command {
+    let errors = validate::validate(&config.content_directory, &config.base_url).await;
+    for error in &errors {
+      println!("{}", error);
+    }
+
+    if !errors.is_empty() {
+      error!("Found {} problems in the content directory", errors.len());
+      std::process::exit(1);
+    }
+
+    info!("Content directory is valid");
+    return Ok(());
+  }
+
+  let shutdown = CancellationToken::new();
+  let metrics = telemetry::install(&shutdown)?;
+  let client = Client::builder().timeout(config.upstream_timeout).build()?;
+  let content_directory = &config.content_directory;
+  let state_directory = config.state_directory.as_deref();
+
   let state = FoundationState {
-    blog: Blogs::load(&args.content_directory.join("blog")).await?,
-    news: News::load(&args.content_directory.join("news")).await?,
-    text_blocks: TextBlocks::load(&args.content_directory.join("text_blocks"), &args.base_url)
-      .await?,
-    documents: Documents::load(&args.content_directory.join("documents")).await?,
-    team: Team::load(&args.content_directory.join("team")).await?,
-    stats: Stats::new(args.prometheus_url),
+    base_url: Arc::new(config.base_url.clone()),
+    languages: Arc::new(Languages::new(fallback_languages)),
+    metrics,
+    preview_token: config.preview_token.as_deref().map(Arc::from),
+    trusted_proxies: Arc::new(TrustedProxies {
+      networks: config.trusted_proxies.clone(),
+      header: config.forwarded_header,
+    }),
+    blog: Blogs::load(&content_directory.join("blog")).await?,
+    news: News::load(&content_directory.join("news")).await?,
+    text_blocks: TextBlocks::load(&content_directory.join("text_blocks"), &config.base_url).await?,
+    documents: Documents::load(&content_directory.join("documents")).await?,
+    team: Team::load(&content_directory.join("team")).await?,
+    stats: Stats::new(client.clone(), &config.stats, state_directory),
     peers: NetworkService::new(
-      &args.content_directory.join("supporter"),
-      args.ixp_manager_url,
+      &content_directory.join("supporter"),
+      client.clone(),
+      &config.peers,
+      state_directory,
     )
     .await?,
-    bird: Bird::new(args.bird_html).await?,
-    events: Events::load(&args.content_directory.join("event")).await?,
-    mirrors: Mirrors::load(&args.content_directory.join("mirrors.yaml")).await?,
-    looking_glass: LookingGlass::load(args.looking_glass_url).await?,
+    bird: Bird::new(&config.bird).await?,
+    events: Events::load(&content_directory.join("event")).await?,
+    mirrors: Mirrors::load(&content_directory.join("mirrors.yaml")).await?,
+    looking_glass: LookingGlass::load(client.clone(), &config.looking_glass, state_directory)
+      .await?,
   };
 
-  let stats = state.stats.clone();
-  tokio::spawn(async move {
-    loop {
-      if let Err(err) = stats.update().await {
-        error!("Failed to update stats: {:?}", err);
-        tokio::time::sleep(Duration::from_secs(10)).await;
-      } else {
-        tokio::time::sleep(Duration::from_secs(60 * 10)).await;
-      }
-    }
-  });
-
-  let looking_glass = state.looking_glass.clone();
-  tokio::spawn(async move {
-    loop {
-      println!("updating cache!");
-      if let Err(e) = looking_glass.routes.get().await {
-        println!("error while updating routes cache: {e}");
-        tokio::time::sleep(Duration::from_secs(10)).await;
-      } else {
-        tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+  watcher::watch(content_directory.clone(), state.clone(), shutdown.clone())?;
+
+  state.stats.spawn_refreshers(&shutdown);
+  state.looking_glass.routes.spawn_refresher(&shutdown);
+
+  let router = route(
+    &ContentPaths {
+      blog: content_directory.join("blog/assets"),
+      event: content_directory.join("event/assets"),
+      news: content_directory.join("news/assets"),
+      text_blocks: content_directory.join("text_blocks/assets"),
+      document: content_directory.join("documents/download"),
+      team: content_directory.join("team/assets"),
+    },
+    CachePolicy {
+      assets: config.http_cache.assets_max_age,
+      content: config.http_cache.content_max_age,
+    },
+    config.metrics_listen_addr.is_none(),
+  )
+  .layer(middleware::from_fn(telemetry::track_requests))
+  .layer(CompressionLayer::new())
+  .with_state(state.clone());
+
+  if let Some(metrics_listen_addr) = config.metrics_listen_addr {
+    let listener = TcpListener::bind(metrics_listen_addr).await?;
+    info!(
+      "Serving metrics on http://{}/metrics...",
+      metrics_listen_addr
+    );
+    let metrics = axum::serve(listener, metrics_route().with_state(state))
+      .with_graceful_shutdown(shutdown.clone().cancelled_owned());
+    tokio::spawn(async move {
+      if let Err(err) = metrics.await {
+        error!("Error while serving metrics: {}", err);
       }
-    }
-  });
+    });
+  }
 
-  let cors = CorsLayer::new()
-    .allow_methods([Method::GET, Method::POST])
-    .allow_headers([CONTENT_TYPE])
-    .allow_origin(Any);
+  let app = ServiceBuilder::new()
+    .layer(middleware::from_fn(telemetry::request_id))
+    .layer(middleware::from_fn_with_state(
+      CorsPolicy::new(&config.cors)?,
+      routes::cors,
+    ))
+    .service(router);
 
-  let router = route(&ContentPaths {
-    blog: args.content_directory.join("blog/assets"),
-    event: args.content_directory.join("event/assets"),
-    news: args.content_directory.join("news/assets"),
-    text_blocks: args.content_directory.join("text_blocks/assets"),
-    document: args.content_directory.join("documents/download"),
-    team: args.content_directory.join("team/assets"),
-  })
-  .layer(cors)
-  .with_state(state);
+  let listener = match systemd::listener()? {
+    Some(listener) => {
+      info!("Listening on socket {}...", listener.local_addr()?);
+      listener
+    }
+    None => {
+      let listener = TcpListener::bind(&config.listen_addr).await?;
+      info!("Listening on http://{}...", config.listen_addr);
+      listener
+    }
+  };
 
-  let listener = TcpListener::bind(&args.listen_addr).await?;
-  info!("Listening on http://{}...", args.listen_addr);
+  let local_addr = listener.local_addr()?;
+  let server = axum::serve(
+    listener,
+    app.into_make_service_with_connect_info::<SocketAddr>(),
+  )
+  .with_graceful_shutdown(shutdown_signal(shutdown.clone()));
 
-  let server = axum::serve(listener, router.into_make_service());
+  systemd::ready(&shutdown, client, local_addr);
 
   if let Err(err) = server.await {
     error!("Error while serving api: {}", err);
   }
 
+  shutdown.cancel();
+  info!("Stopped");
+
   Ok(())
 }
+
+/// Waits for SIGINT or SIGTERM, then stops the background tasks while the
+/// server finishes the requests in flight.
+async fn shutdown_signal(shutdown: CancellationToken) {
+  let terminate = async {
+    match signal(SignalKind::terminate()) {
+      Ok(mut terminate) => {
+        terminate.recv().await;
+      }
+      Err(err) => {
+        error!("Cannot listen for SIGTERM: {}", err);
+        std::future::pending::<()>().await;
+      }
+    }
+  };
+
+  tokio::select! {
+    _ = tokio::signal::ctrl_c() => {}
+    _ = terminate => {}
+  }
+
+  info!("Shutting down, waiting for open requests...");
+  systemd::stopping();
+  shutdown.cancel();
+}
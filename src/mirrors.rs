@@ -1,6 +1,10 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::validate::{read_yaml, ContentError};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct Mirror {
@@ -11,20 +15,39 @@ pub(crate) struct Mirror {
 
 #[derive(Clone)]
 pub(crate) struct Mirrors {
-  mirrors: Vec<Mirror>,
+  file: Arc<PathBuf>,
+  mirrors: Arc<RwLock<Arc<Vec<Mirror>>>>,
 }
 
 impl Mirrors {
   pub(crate) async fn load(file: impl AsRef<Path>) -> anyhow::Result<Self> {
-    let serialized_mirrors = tokio::fs::read_to_string(file).await?;
-    let mut mirrors: Vec<Mirror> = serde_yaml_ng::from_str(&serialized_mirrors)?;
+    let mirrors = read_mirrors(file.as_ref()).await?;
+
+    Ok(Self {
+      file: Arc::new(file.as_ref().to_path_buf()),
+      mirrors: Arc::new(RwLock::new(Arc::new(mirrors))),
+    })
+  }
 
-    mirrors.sort_by(|a, b| a.name.cmp(&b.name));
+  pub(crate) async fn reload(&self) -> anyhow::Result<()> {
+    let mirrors = read_mirrors(&self.file).await?;
+    *self.mirrors.write().await = Arc::new(mirrors);
+    Ok(())
+  }
 
-    Ok(Self { mirrors })
+  pub(crate) async fn validate(file: &Path) -> Vec<ContentError> {
+    read_mirrors(file).await.err().into_iter().collect()
   }
 
-  pub(crate) fn mirrors(&self) -> Vec<Mirror> {
-    self.mirrors.to_vec()
+  pub(crate) async fn mirrors(&self) -> Vec<Mirror> {
+    self.mirrors.read().await.to_vec()
   }
 }
+
+async fn read_mirrors(file: &Path) -> Result<Vec<Mirror>, ContentError> {
+  let mut mirrors: Vec<Mirror> = read_yaml(file).await?;
+
+  mirrors.sort_by(|a, b| a.name.cmp(&b.name));
+
+  Ok(mirrors)
+}
@@ -2,10 +2,12 @@ use std::sync::Arc;
 
 use crate::lang::Language;
 use crate::posts::{
-  post_provider::{LongPostFromMeta, PostMeta, PostProvider},
-  MyDate,
+  feed::FeedPost,
+  post_provider::{LongPostFromMeta, PostContent, PostMeta, PostProvider, Translatable},
+  Alternates, MyDate, Publication,
 };
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 pub(crate) type News = PostProvider<NewsMeta, SmallNewsPost, NewsPost>;
 
@@ -18,6 +20,9 @@ pub(crate) struct NewsMeta {
   keywords: Vec<String>,
   authors: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
+  translation_key: Option<String>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -32,6 +37,11 @@ pub(crate) struct NewsPost {
   keywords: Vec<String>,
   authors: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
+  #[serde(skip)]
+  translation_key: Option<String>,
+  alternates: Alternates,
   body: String,
 }
 
@@ -47,6 +57,8 @@ pub(crate) struct SmallNewsPost {
   keywords: Vec<String>,
   authors: Vec<String>,
   image: Option<String>,
+  #[serde(flatten)]
+  publication: Publication,
 }
 
 impl LongPostFromMeta<NewsMeta> for NewsPost {
@@ -62,6 +74,9 @@ impl LongPostFromMeta<NewsMeta> for NewsPost {
       keywords: meta.keywords,
       authors: meta.authors,
       image: meta.image,
+      publication: meta.publication,
+      translation_key: meta.translation_key,
+      alternates: Alternates::new(),
       body,
     }
   }
@@ -80,6 +95,7 @@ impl From<Arc<NewsPost>> for SmallNewsPost {
       keywords: post.keywords.clone(),
       authors: post.authors.clone(),
       image: post.image.clone(),
+      publication: post.publication,
     }
   }
 }
@@ -100,6 +116,18 @@ impl PostMeta for SmallNewsPost {
   fn keywords(&self) -> &Vec<String> {
     &self.keywords
   }
+
+  fn publication(&self) -> Publication {
+    self.publication
+  }
+
+  fn date(&self) -> OffsetDateTime {
+    self.published.midnight_utc()
+  }
+
+  fn last_modified(&self) -> Option<OffsetDateTime> {
+    Some(self.modified.unwrap_or(self.published).midnight_utc())
+  }
 }
 
 impl PostMeta for NewsPost {
@@ -118,4 +146,58 @@ impl PostMeta for NewsPost {
   fn keywords(&self) -> &Vec<String> {
     &self.keywords
   }
+
+  fn publication(&self) -> Publication {
+    self.publication
+  }
+
+  fn date(&self) -> OffsetDateTime {
+    self.published.midnight_utc()
+  }
+
+  fn last_modified(&self) -> Option<OffsetDateTime> {
+    Some(self.modified.unwrap_or(self.published).midnight_utc())
+  }
+}
+
+impl Translatable for NewsPost {
+  fn explicit_translation_key(&self) -> Option<&str> {
+    self.translation_key.as_deref()
+  }
+
+  fn alternates(&self) -> &Alternates {
+    &self.alternates
+  }
+
+  fn set_alternates(&mut self, alternates: Alternates) {
+    self.alternates = alternates;
+  }
+}
+
+impl PostContent for NewsPost {
+  fn title(&self) -> &str {
+    &self.title
+  }
+
+  fn description(&self) -> &str {
+    &self.description
+  }
+
+  fn body(&self) -> &str {
+    &self.body
+  }
+}
+
+impl FeedPost for NewsPost {
+  fn published(&self) -> MyDate {
+    self.published
+  }
+
+  fn modified(&self) -> Option<MyDate> {
+    self.modified
+  }
+
+  fn authors(&self) -> &[String] {
+    &self.authors
+  }
 }
@@ -7,8 +7,9 @@ use serde::{Deserialize, Serialize};
 use tracing::info;
 use url::Url;
 
-use crate::cache::Cache;
-use crate::cache::Updater;
+use crate::cache::{Cache, CacheStatus, Cached, RefreshPolicy, Updater};
+use crate::config::PeersConfig;
+use crate::validate::{read_yaml, ContentError};
 
 // https://github.com/euro-ix/json-schemas/wiki/Schema-Field-Entries-Members#schema-field-entries---members
 
@@ -65,13 +66,13 @@ struct StaticSupporterInformation {
   supporting_peers: Vec<i32>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 struct ConnectionSpeed {
   speed: u64,
   amount: u64,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct FoundationEntity {
   supporter: bool,
   rs_v4: bool,
@@ -176,21 +177,38 @@ impl Updater for PeersUpdater {
 }
 
 impl NetworkService {
-  pub(crate) async fn new(base_path: &Path, ixp_manager_url: Url) -> anyhow::Result<Self> {
-    let serialized_supporter = tokio::fs::read_to_string(base_path.join("supporter.yaml")).await?;
-    let yaml_file = serde_yaml_ng::from_str(&serialized_supporter)?;
+  pub(crate) async fn new(
+    base_path: &Path,
+    client: Client,
+    config: &PeersConfig,
+    state_directory: Option<&Path>,
+  ) -> anyhow::Result<Self> {
+    let yaml_file = read_yaml(&base_path.join("supporter.yaml")).await?;
 
     let updater = PeersUpdater {
-      client: Client::new(),
-      ixp_manager_url,
+      client,
+      ixp_manager_url: config.ixp_manager_url.clone(),
       yaml_file,
     };
+    let refresh = RefreshPolicy::every(config.refresh_interval);
 
     Ok(Self {
-      cached: Arc::new(Cache::new(updater)),
+      cached: Arc::new(Cache::new("peers", refresh, updater).persist_in(state_directory)),
     })
   }
-  pub(crate) async fn get_stats(&self) -> anyhow::Result<Arc<Vec<FoundationEntity>>> {
+  pub(crate) async fn validate(base_path: &Path) -> Vec<ContentError> {
+    read_yaml::<StaticSupporterInformation>(&base_path.join("supporter.yaml"))
+      .await
+      .err()
+      .into_iter()
+      .collect()
+  }
+
+  pub(crate) fn status(&self) -> CacheStatus {
+    self.cached.status()
+  }
+
+  pub(crate) async fn get_stats(&self) -> anyhow::Result<Cached<Arc<Vec<FoundationEntity>>>> {
     self.cached.get().await
   }
 }
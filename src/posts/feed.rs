@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use atom_syndication::{
+  CategoryBuilder as AtomCategoryBuilder, ContentBuilder, EntryBuilder, FeedBuilder, FixedDateTime,
+  LinkBuilder, PersonBuilder,
+};
+use rss::{CategoryBuilder, ChannelBuilder, GuidBuilder, ItemBuilder};
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+use url::Url;
+
+use super::post_provider::{PostContent, PostMeta};
+use super::MyDate;
+use crate::lang::Language;
+
+pub trait FeedPost: PostMeta + PostContent {
+  fn published(&self) -> MyDate;
+  fn modified(&self) -> Option<MyDate>;
+  fn authors(&self) -> &[String];
+}
+
+/// A syndication feed over the posts of one section (e.g. `blog`) in one
+/// language.
+pub(crate) struct Feed<'a, Post> {
+  pub(crate) title: &'a str,
+  pub(crate) section: &'a str,
+  pub(crate) lang: Language,
+  pub(crate) base_url: &'a Url,
+  pub(crate) posts: Vec<Arc<Post>>,
+}
+
+impl<Post: FeedPost> Feed<'_, Post> {
+  pub(crate) fn rss(&self) -> anyhow::Result<String> {
+    let items = self
+      .posts
+      .iter()
+      .map(|post| {
+        let link = self.post_url(post.as_ref())?.to_string();
+
+        Ok(
+          ItemBuilder::default()
+            .title(post.title().to_string())
+            .link(link.clone())
+            .guid(GuidBuilder::default().value(link).permalink(true).build())
+            .description(post.description().to_string())
+            .content(post.body().to_string())
+            .pub_date(post.published().midnight_utc().format(&Rfc2822)?)
+            .categories(
+              post
+                .keywords()
+                .iter()
+                .map(|keyword| CategoryBuilder::default().name(keyword).build())
+                .collect::<Vec<_>>(),
+            )
+            .build(),
+        )
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut channel = ChannelBuilder::default()
+      .title(self.title)
+      .link(self.section_url()?.to_string())
+      .description(self.title)
+      .language(self.lang.code().to_string())
+      .items(items)
+      .build();
+
+    if let Some(updated) = self.updated() {
+      channel.set_last_build_date(updated.format(&Rfc2822)?);
+    }
+
+    Ok(channel.to_string())
+  }
+
+  pub(crate) fn atom(&self) -> anyhow::Result<String> {
+    let entries = self
+      .posts
+      .iter()
+      .map(|post| {
+        let link = self.post_url(post.as_ref())?.to_string();
+        let published = post.published().midnight_utc();
+        let updated = post.modified().map_or(published, MyDate::midnight_utc);
+
+        Ok(
+          EntryBuilder::default()
+            .id(link.clone())
+            .title(post.title())
+            .links(vec![LinkBuilder::default().href(link).build()])
+            .published(Some(fixed_date_time(published)?))
+            .updated(fixed_date_time(updated)?)
+            .authors(
+              post
+                .authors()
+                .iter()
+                .map(|author| PersonBuilder::default().name(author).build())
+                .collect::<Vec<_>>(),
+            )
+            .categories(
+              post
+                .keywords()
+                .iter()
+                .map(|keyword| AtomCategoryBuilder::default().term(keyword).build())
+                .collect::<Vec<_>>(),
+            )
+            .summary(Some(post.description().into()))
+            .content(Some(
+              ContentBuilder::default()
+                .content_type(Some("html".to_string()))
+                .value(Some(post.body().to_string()))
+                .build(),
+            ))
+            .build(),
+        )
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let section_url = self.section_url()?.to_string();
+
+    let feed = FeedBuilder::default()
+      .id(section_url.clone())
+      .title(self.title)
+      .lang(Some(self.lang.code().to_string()))
+      .links(vec![
+        LinkBuilder::default().href(section_url).build(),
+        LinkBuilder::default()
+          .href(
+            self
+              .base_url
+              .join(&format!("/{}/{}/atom.xml", self.section, self.lang.code()))?,
+          )
+          .rel("self")
+          .build(),
+      ])
+      .updated(fixed_date_time(
+        self.updated().unwrap_or(OffsetDateTime::UNIX_EPOCH),
+      )?)
+      .entries(entries)
+      .build();
+
+    Ok(feed.to_string())
+  }
+
+  fn section_url(&self) -> anyhow::Result<Url> {
+    Ok(
+      self
+        .base_url
+        .join(&format!("/{}/{}", self.section, self.lang.code()))?,
+    )
+  }
+
+  fn post_url(&self, post: &Post) -> anyhow::Result<Url> {
+    Ok(self.base_url.join(&format!(
+      "/{}/{}/{}",
+      self.section,
+      self.lang.code(),
+      post.slug()
+    ))?)
+  }
+
+  fn updated(&self) -> Option<OffsetDateTime> {
+    self
+      .posts
+      .iter()
+      .map(|post| post.modified().unwrap_or(post.published()).midnight_utc())
+      .max()
+  }
+}
+
+fn fixed_date_time(date_time: OffsetDateTime) -> anyhow::Result<FixedDateTime> {
+  FixedDateTime::parse_from_rfc3339(&date_time.format(&Rfc3339)?)
+    .map_err(|err| anyhow!("Invalid date {}: {}", date_time, err))
+}
@@ -2,13 +2,54 @@ use crate::lang::Language;
 use anyhow::anyhow;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use time::Date;
+use std::collections::BTreeMap;
+use time::{Date, OffsetDateTime};
 
+pub mod feed;
 pub mod post_provider;
+pub mod search;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) struct MyDate(Date);
 
+/// Frontmatter fields deciding whether a post is publicly visible yet.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Publication {
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  draft: bool,
+  #[serde(
+    default,
+    with = "time::serde::rfc3339::option",
+    skip_serializing_if = "Option::is_none"
+  )]
+  publish_at: Option<OffsetDateTime>,
+}
+
+/// Slugs of all translations of a post by language, e.g. for a language
+/// switcher or `hreflang` links.
+pub(crate) type Alternates = BTreeMap<Language, String>;
+
+impl Publication {
+  pub(crate) fn is_draft(&self) -> bool {
+    self.draft
+  }
+
+  pub(crate) fn is_visible(&self, now: OffsetDateTime) -> bool {
+    !self.draft && self.publish_at.is_none_or(|publish_at| publish_at <= now)
+  }
+
+  /// Time a scheduled post becomes visible.
+  pub(crate) fn publish_at(&self) -> Option<OffsetDateTime> {
+    self.publish_at
+  }
+}
+
+impl MyDate {
+  pub(crate) fn midnight_utc(self) -> OffsetDateTime {
+    self.0.midnight().assume_utc()
+  }
+}
+
 pub(crate) fn parse_file_name(file_name: &str) -> anyhow::Result<(u32, Language, &str)> {
   let mut split = file_name.split('.');
 
@@ -1,6 +1,7 @@
-use super::parse_file_name;
-use std::collections::HashSet;
-use std::path::Path;
+use super::search::SearchIndex;
+use super::{parse_file_name, Alternates, Publication};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use asciidork_parser::parser::SourceFile;
@@ -8,9 +9,13 @@ use asciidork_parser::prelude::Bump;
 use asciidork_parser::Parser;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use time::OffsetDateTime;
+use tokio::sync::RwLock;
 use tracing::info;
 
+use crate::conditional::{ETag, Validators};
 use crate::lang::Language;
+use crate::validate::{list_files, ContentError, ContentErrors};
 
 pub trait LongPostFromMeta<Meta> {
   fn from(slug: &str, lang: Language, idx: u32, meta: Meta, body: String) -> Self;
@@ -21,6 +26,32 @@ pub trait PostMeta {
   fn lang(&self) -> Language;
   fn slug(&self) -> &str;
   fn keywords(&self) -> &Vec<String>;
+  fn publication(&self) -> Publication;
+  /// Date used for sorting, the publish date of posts or the start of events.
+  fn date(&self) -> OffsetDateTime;
+  /// Date the content was last changed, if the post records one.
+  fn last_modified(&self) -> Option<OffsetDateTime>;
+}
+
+pub trait Translatable: PostMeta {
+  /// Translation key set explicitly in the frontmatter.
+  fn explicit_translation_key(&self) -> Option<&str>;
+  fn alternates(&self) -> &Alternates;
+  fn set_alternates(&mut self, alternates: Alternates);
+
+  /// Key shared by all translations of a post, the index of the post unless
+  /// the frontmatter sets a `translation_key`.
+  fn translation_key(&self) -> String {
+    self
+      .explicit_translation_key()
+      .map_or_else(|| self.idx().to_string(), str::to_string)
+  }
+}
+
+pub trait PostContent {
+  fn title(&self) -> &str;
+  fn description(&self) -> &str;
+  fn body(&self) -> &str;
 }
 
 #[derive(Debug, Clone)]
@@ -29,61 +60,122 @@ where
   LongPost: LongPostFromMeta<Meta>,
   ShortPost: From<Arc<LongPost>>,
 {
-  posts: Arc<Vec<Arc<LongPost>>>,
-  small_posts: Arc<Vec<Arc<ShortPost>>>,
+  directory: Arc<PathBuf>,
+  snapshot: Arc<RwLock<Arc<Posts<ShortPost, LongPost>>>>,
   _meta: Arc<Vec<Arc<Meta>>>,
 }
 
+#[derive(Debug)]
+struct Posts<ShortPost, LongPost> {
+  posts: Vec<Arc<LongPost>>,
+  small_posts: Vec<Arc<ShortPost>>,
+  index: SearchIndex,
+  /// Version of every post by language and slug.
+  versions: HashMap<(Language, String), Version>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Version {
+  /// Hash of the JSON of the post.
+  etag: ETag,
+  /// Modification time of the file, the frontmatter dates are not bumped for
+  /// every edit.
+  modified: OffsetDateTime,
+}
+
 impl<Meta, ShortPost, LongPost> PostProvider<Meta, ShortPost, LongPost>
 where
-  LongPost: Serialize + LongPostFromMeta<Meta> + PostMeta + std::fmt::Debug,
+  LongPost: Serialize
+    + LongPostFromMeta<Meta>
+    + PostMeta
+    + PostContent
+    + Translatable
+    + Clone
+    + std::fmt::Debug,
   ShortPost: Serialize + From<Arc<LongPost>> + PostMeta + PartialEq,
   Meta: DeserializeOwned + Clone,
 {
   pub(crate) async fn load(directory: &Path) -> anyhow::Result<Self> {
-    let mut posts = Vec::new();
+    let snapshot = Self::read_posts(directory).await?;
 
-    let mut dir = tokio::fs::read_dir(directory).await?;
-    while let Some(entry) = dir.next_entry().await? {
-      if entry.file_type().await?.is_dir() {
-        continue;
-      }
+    Ok(Self {
+      directory: Arc::new(directory.to_path_buf()),
+      snapshot: Arc::new(RwLock::new(Arc::new(snapshot))),
+      _meta: Arc::new(Vec::new()),
+    })
+  }
 
-      let path = entry.path();
-      let content = tokio::fs::read_to_string(path.as_path()).await?;
-      let content = content.trim_start();
-      let content = content.strip_prefix("---").unwrap();
-      let (meta, text) = content.split_once("---").unwrap();
+  /// Re-reads the post directory and swaps in the new posts. If reading fails
+  /// the previously loaded posts are kept.
+  pub(crate) async fn reload(&self) -> anyhow::Result<()> {
+    let snapshot = Self::read_posts(&self.directory).await?;
+    *self.snapshot.write().await = Arc::new(snapshot);
+    Ok(())
+  }
 
-      let meta: Meta = serde_yaml_ng::from_str::<Meta>(meta)?;
-      let file_name = path.file_name().unwrap().to_str().unwrap();
+  async fn snapshot(&self) -> Arc<Posts<ShortPost, LongPost>> {
+    self.snapshot.read().await.clone()
+  }
 
-      if file_name.starts_with('_') {
-        continue;
-      }
+  /// Checks every post of the directory and that each post is translated into
+  /// every language.
+  pub(crate) async fn validate(directory: &Path) -> Vec<ContentError> {
+    let (files, mut errors) = Self::read_files(directory).await;
+    let posts = files.into_iter().map(|(post, _)| post).collect::<Vec<_>>();
 
-      let is_adoc_file = file_name.ends_with(".adoc");
+    let mut translations: BTreeMap<String, (&str, HashSet<Language>)> = BTreeMap::new();
+    for post in &posts {
+      let key = post.translation_key();
+      let (slug, langs) = translations
+        .entry(key.clone())
+        .or_insert_with(|| (post.slug(), HashSet::new()));
 
-      info!(
-        "reading news post: {} is adoc: {}",
-        &file_name, &is_adoc_file
-      );
-      let (idx, lang, slug) = parse_file_name(file_name)?;
+      if !langs.insert(post.lang()) {
+        errors.push(ContentError::new(
+          directory,
+          None,
+          format!(
+            "Post {} ({}) has more than one {} translation",
+            key,
+            slug,
+            post.lang().code()
+          ),
+        ));
+      }
+    }
 
-      let body = if is_adoc_file {
-        let bump = &Bump::with_capacity(text.len() * 2);
-        let parsed_adoc = Parser::from_str(text, SourceFile::Path(path.clone().into()), bump)
-          .parse()
-          .expect("cannot parse adoc!");
+    for (key, (slug, langs)) in translations {
+      for lang in Language::all()
+        .iter()
+        .copied()
+        .filter(|lang| !langs.contains(lang))
+      {
+        errors.push(ContentError::new(
+          directory,
+          None,
+          format!("Post {} ({}) has no {} translation", key, slug, lang.code()),
+        ));
+      }
+    }
 
-        asciidork_dr_html_backend::convert(parsed_adoc.document).expect("cannot render adoc!")
-      } else {
-        markdown::to_html(text)
-      };
+    errors
+  }
+
+  async fn read_posts(directory: &Path) -> Result<Posts<ShortPost, LongPost>, ContentErrors> {
+    let (files, errors) = Self::read_files(directory).await;
+    if !errors.is_empty() {
+      return Err(ContentErrors(errors));
+    }
 
-      posts.push(Arc::new(LongPost::from(slug, lang, idx, meta, body)));
+    let mut modified = HashMap::new();
+    let mut posts = Vec::with_capacity(files.len());
+    for (post, file_modified) in files {
+      modified.insert((post.lang(), post.slug().to_string()), file_modified);
+      posts.push(post);
     }
 
+    link_translations(&mut posts);
+    let mut posts = posts.into_iter().map(Arc::new).collect::<Vec<_>>();
     posts.sort_by_key(|b| std::cmp::Reverse(b.idx()));
 
     let small_posts = posts
@@ -91,15 +183,180 @@ where
       .map(|post| Arc::new(ShortPost::from(post.clone())))
       .collect();
 
-    Ok(Self {
-      posts: Arc::new(posts),
-      small_posts: Arc::new(small_posts),
-      _meta: Arc::new(Vec::new()),
+    let index = SearchIndex::build(posts.iter().map(Arc::as_ref));
+
+    let versions = posts
+      .iter()
+      .map(|post| {
+        let json = serde_json::to_vec(post.as_ref())
+          .map_err(|err| ContentError::new(directory, None, err))?;
+        let key = (post.lang(), post.slug().to_string());
+        let version = Version {
+          etag: ETag::of(json),
+          modified: modified[&key],
+        };
+        Ok((key, version))
+      })
+      .collect::<Result<_, ContentError>>()?;
+
+    Ok(Posts {
+      posts,
+      small_posts,
+      index,
+      versions,
     })
   }
 
+  /// Reads all posts of the directory, collecting the problems of every file
+  /// instead of stopping at the first one. Every post comes with the
+  /// modification time of its file.
+  async fn read_files(directory: &Path) -> (Vec<(LongPost, OffsetDateTime)>, Vec<ContentError>) {
+    let mut posts = Vec::new();
+    let mut errors = Vec::new();
+
+    let paths = match list_files(directory).await {
+      Ok(paths) => paths,
+      Err(err) => return (posts, vec![ContentError::new(directory, None, err)]),
+    };
+
+    for path in paths {
+      match Self::read_post(&path).await {
+        Ok(Some(post)) => match tokio::fs::metadata(&path)
+          .await
+          .and_then(|meta| meta.modified())
+        {
+          Ok(modified) => posts.push((post, OffsetDateTime::from(modified))),
+          Err(err) => errors.push(ContentError::new(&path, None, err)),
+        },
+        Ok(None) => {}
+        Err(mut err) => errors.append(&mut err.0),
+      }
+    }
+
+    (posts, errors)
+  }
+
+  async fn read_post(path: &Path) -> Result<Option<LongPost>, ContentErrors> {
+    let file_name = path
+      .file_name()
+      .and_then(|file_name| file_name.to_str())
+      .ok_or_else(|| ContentError::new(path, None, "File name is not valid UTF-8"))?;
+
+    if file_name.starts_with('_') {
+      return Ok(None);
+    }
+
+    let (idx, lang, slug) =
+      parse_file_name(file_name).map_err(|err| ContentError::new(path, None, err))?;
+
+    let content = tokio::fs::read_to_string(path)
+      .await
+      .map_err(|err| ContentError::new(path, None, err))?;
+    let trimmed = content.trim_start();
+    // number of lines before the opening delimiter
+    let offset = content[..content.len() - trimmed.len()]
+      .matches('\n')
+      .count();
+
+    let (meta, text) = trimmed
+      .strip_prefix("---")
+      .ok_or_else(|| {
+        ContentError::new(path, Some(offset + 1), "Frontmatter has to start with ---")
+      })?
+      .split_once("---")
+      .ok_or_else(|| {
+        ContentError::new(path, Some(offset + 1), "Frontmatter is not closed by ---")
+      })?;
+
+    // pad the frontmatter, so that reported lines match the lines of the file
+    let parsed_meta: Meta = serde_yaml_ng::from_str(&format!("{}{}", "\n".repeat(offset), meta))
+      .map_err(|err| {
+        ContentError::new(path, err.location().map(|location| location.line()), err)
+      })?;
+
+    let is_adoc_file = file_name.ends_with(".adoc");
+
+    info!(
+      "reading news post: {} is adoc: {}",
+      &file_name, &is_adoc_file
+    );
+
+    let body = if is_adoc_file {
+      // the text starts on the line of the closing delimiter
+      let text_offset = offset + meta.matches('\n').count();
+
+      let bump = &Bump::with_capacity(text.len() * 2);
+      let parsed_adoc = Parser::from_str(text, SourceFile::Path(path.to_path_buf().into()), bump)
+        .parse()
+        .map_err(|diagnostics| {
+          ContentErrors(
+            diagnostics
+              .into_iter()
+              .map(|diagnostic| {
+                ContentError::new(
+                  path,
+                  Some(text_offset + diagnostic.line_num as usize),
+                  diagnostic.message,
+                )
+              })
+              .collect(),
+          )
+        })?;
+
+      asciidork_dr_html_backend::convert(parsed_adoc.document)
+        .map_err(|err| ContentError::new(path, None, format!("Cannot render adoc: {}", err)))?
+    } else {
+      markdown::to_html(text)
+    };
+
+    Ok(Some(LongPost::from(slug, lang, idx, parsed_meta, body)))
+  }
+
   pub async fn content_by_lang(&self, lang: Language) -> Vec<Arc<ShortPost>> {
+    let now = OffsetDateTime::now_utc();
+    self
+      .snapshot()
+      .await
+      .small_posts
+      .iter()
+      .filter(|post| post.lang() == lang && post.publication().is_visible(now))
+      .cloned()
+      .collect()
+  }
+
+  /// Finds the post with the given slug, or one of its translations, in the
+  /// first language of `langs` it is available in.
+  pub async fn translation_by_slug(&self, langs: &[Language], slug: &str) -> Option<Arc<LongPost>> {
+    let now = OffsetDateTime::now_utc();
+    let snapshot = self.snapshot().await;
+    let visible = |lang: Language, slug: &str| {
+      snapshot.posts.iter().find(|post| {
+        post.lang() == lang && post.slug() == slug && post.publication().is_visible(now)
+      })
+    };
+
+    // prefer a post in the requested language if slugs collide across languages
+    let post = langs
+      .iter()
+      .find_map(|lang| visible(*lang, slug))
+      .or_else(|| {
+        snapshot
+          .posts
+          .iter()
+          .find(|post| post.slug() == slug && post.publication().is_visible(now))
+      })?;
+
+    let post = langs
+      .iter()
+      .find_map(|lang| visible(*lang, post.alternates().get(lang)?))?;
+    Some(snapshot.with_visible_alternates(post, now))
+  }
+
+  /// Like [`Self::content_by_lang`] but including drafts and scheduled posts.
+  pub async fn preview_by_lang(&self, lang: Language) -> Vec<Arc<ShortPost>> {
     self
+      .snapshot()
+      .await
       .small_posts
       .iter()
       .filter(|post| post.lang() == lang)
@@ -107,23 +364,40 @@ where
       .collect()
   }
 
-  pub async fn content_by_slug(&self, lang: Language, slug: &str) -> Option<Arc<LongPost>> {
+  /// Finds a post by language and slug, including drafts and scheduled posts.
+  pub async fn preview_by_slug(&self, lang: Language, slug: &str) -> Option<Arc<LongPost>> {
     self
+      .snapshot()
+      .await
       .posts
       .iter()
       .find(|post| post.lang() == lang && post.slug() == slug)
       .cloned()
   }
 
+  /// Resolves short posts, e.g. from [`Self::content_by_lang`], to their full
+  /// version including the rendered body.
+  pub async fn long_posts(&self, posts: &[Arc<ShortPost>]) -> Vec<Arc<LongPost>> {
+    let now = OffsetDateTime::now_utc();
+    let snapshot = self.snapshot().await;
+    posts
+      .iter()
+      .filter_map(|short| snapshot.find(short.lang(), short.slug()))
+      .map(|post| snapshot.with_visible_alternates(post, now))
+      .collect()
+  }
+
   pub async fn search_by_keywords(
     &self,
     lang: Language,
     keywords: &[String],
   ) -> Vec<Arc<ShortPost>> {
-    let posts = self
+    let now = OffsetDateTime::now_utc();
+    let snapshot = self.snapshot().await;
+    let posts = snapshot
       .small_posts
       .iter()
-      .filter(|post| post.lang() == lang)
+      .filter(|post| post.lang() == lang && post.publication().is_visible(now))
       .collect::<Vec<_>>();
 
     let keywords_set = keywords.iter().collect::<HashSet<_>>();
@@ -164,11 +438,161 @@ where
     or
   }
 
+  /// Full text search over title, description and body, best match first.
+  pub async fn search(&self, lang: Language, query: &str) -> Vec<(f32, Arc<ShortPost>)> {
+    let now = OffsetDateTime::now_utc();
+    let snapshot = self.snapshot().await;
+    snapshot
+      .index
+      .search(lang, query, |idx| {
+        snapshot.small_posts[idx].publication().is_visible(now)
+      })
+      .into_iter()
+      .map(|(idx, score)| (score, snapshot.small_posts[idx].clone()))
+      .collect()
+  }
+
+  /// Validators of a response listing `posts` in `lang`. A listing only has
+  /// an entity tag, as removing or hiding a post changes it without moving any
+  /// modification date forward.
+  pub(crate) async fn validators<Post: PostMeta>(
+    &self,
+    lang: Language,
+    posts: &[Arc<Post>],
+  ) -> Validators {
+    Validators {
+      last_modified: None,
+      ..self.validators_of(lang, posts).await
+    }
+  }
+
+  /// Validators of a response with a single `post`.
+  pub(crate) async fn post_validators<Post: PostMeta>(&self, post: &Arc<Post>) -> Validators {
+    self
+      .validators_of(post.lang(), std::slice::from_ref(post))
+      .await
+  }
+
+  /// Combines the hashes computed when the posts were loaded.
+  async fn validators_of<Post: PostMeta>(&self, lang: Language, posts: &[Arc<Post>]) -> Validators {
+    let now = OffsetDateTime::now_utc();
+    let snapshot = self.snapshot().await;
+    let versions = posts
+      .iter()
+      .filter_map(|post| {
+        snapshot
+          .versions
+          .get(&(post.lang(), post.slug().to_string()))
+          .map(|version| (post, version))
+      })
+      .collect::<Vec<_>>();
+
+    // scheduled translations are added to the alternates once they are visible
+    let translations_published = posts
+      .iter()
+      .filter_map(|post| snapshot.find(post.lang(), post.slug()))
+      .flat_map(|post| post.alternates().iter())
+      .filter_map(|(lang, slug)| snapshot.find(*lang, slug))
+      .filter_map(|translation| translation.publication().publish_at())
+      .filter(|publish_at| *publish_at <= now)
+      .collect::<Vec<_>>();
+
+    // scheduled posts change once they become visible, and files may be
+    // edited without bumping the date in their frontmatter
+    let last_modified = versions
+      .iter()
+      .flat_map(|(post, version)| {
+        [
+          Some(version.modified),
+          post.last_modified(),
+          post.publication().publish_at(),
+        ]
+      })
+      .flatten()
+      .chain(translations_published.iter().copied())
+      .max();
+
+    Validators {
+      etag: ETag::combine(
+        [ETag::of(lang), ETag::of(&translations_published)]
+          .into_iter()
+          .chain(versions.iter().map(|(_, version)| version.etag)),
+      ),
+      last_modified,
+    }
+  }
+
+  /// Number of published posts per language.
+  pub(crate) async fn count_by_lang(&self) -> BTreeMap<Language, usize> {
+    let now = OffsetDateTime::now_utc();
+    let mut counts = BTreeMap::new();
+    for post in self.snapshot().await.small_posts.iter() {
+      if post.publication().is_visible(now) {
+        *counts.entry(post.lang()).or_default() += 1;
+      }
+    }
+    counts
+  }
+
   pub(crate) async fn keywords(&self) -> HashSet<String> {
+    let now = OffsetDateTime::now_utc();
     self
+      .snapshot()
+      .await
       .small_posts
       .iter()
+      .filter(|post| post.publication().is_visible(now))
       .flat_map(|post| post.keywords().clone())
       .collect()
   }
 }
+
+impl<ShortPost, LongPost: Translatable + Clone> Posts<ShortPost, LongPost> {
+  fn find(&self, lang: Language, slug: &str) -> Option<&Arc<LongPost>> {
+    self
+      .posts
+      .iter()
+      .find(|post| post.lang() == lang && post.slug() == slug)
+  }
+
+  /// `post` with only the translations visible at `now` as alternates, so
+  /// the slugs of scheduled translations are not revealed early.
+  fn with_visible_alternates(&self, post: &Arc<LongPost>, now: OffsetDateTime) -> Arc<LongPost> {
+    let alternates: Alternates = post
+      .alternates()
+      .iter()
+      .filter(|(lang, slug)| {
+        self
+          .find(**lang, slug)
+          .is_some_and(|translation| translation.publication().is_visible(now))
+      })
+      .map(|(lang, slug)| (*lang, slug.clone()))
+      .collect();
+
+    if &alternates == post.alternates() {
+      return post.clone();
+    }
+    let mut post = LongPost::clone(post);
+    post.set_alternates(alternates);
+    Arc::new(post)
+  }
+}
+
+/// Sets the alternates of every post to the slugs of all non-draft posts
+/// sharing its translation key. Responses outside of previews only list the
+/// translations visible at the time, see [`Posts::with_visible_alternates`].
+fn link_translations<Post: Translatable>(posts: &mut [Post]) {
+  let mut translations: HashMap<String, Alternates> = HashMap::new();
+  for post in posts.iter().filter(|post| !post.publication().is_draft()) {
+    translations
+      .entry(post.translation_key())
+      .or_default()
+      .insert(post.lang(), post.slug().to_string());
+  }
+
+  for post in posts {
+    if let Some(alternates) = translations.get(&post.translation_key()) {
+      post.set_alternates(alternates.clone());
+    }
+  }
+}
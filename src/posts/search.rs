@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use rust_stemmers::{Algorithm, Stemmer};
+
+use super::post_provider::{PostContent, PostMeta};
+use crate::lang::Language;
+
+// BM25 tuning parameters
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+// how much more a term counts when it appears in the title or description
+const TITLE_WEIGHT: f32 = 3.0;
+const DESCRIPTION_WEIGHT: f32 = 2.0;
+const BODY_WEIGHT: f32 = 1.0;
+
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new("<[^>]*>").unwrap());
+
+struct Posting {
+  post: usize,
+  frequency: f32,
+}
+
+#[derive(Default)]
+struct LanguageIndex {
+  terms: HashMap<String, Vec<Posting>>,
+  lengths: HashMap<usize, f32>,
+}
+
+/// Inverted index over title, description and body of a list of posts,
+/// ranking matches using BM25. The statistics are computed at query time over
+/// the visible posts only, so that unpublished posts do not affect the
+/// ranking.
+#[derive(Default)]
+pub(crate) struct SearchIndex {
+  languages: HashMap<Language, LanguageIndex>,
+}
+
+impl std::fmt::Debug for SearchIndex {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("SearchIndex")
+      .field("languages", &self.languages.len())
+      .finish()
+  }
+}
+
+impl SearchIndex {
+  /// Builds the index, postings refer to the position of the post in `posts`.
+  pub(crate) fn build<'a, Post>(posts: impl IntoIterator<Item = &'a Post>) -> Self
+  where
+    Post: PostMeta + PostContent + 'a,
+  {
+    let mut index = SearchIndex::default();
+
+    for (idx, post) in posts.into_iter().enumerate() {
+      let lang = post.lang();
+      let language_index = index.languages.entry(lang).or_default();
+
+      let mut frequencies: HashMap<String, f32> = HashMap::new();
+      let mut length = 0.0;
+      for (text, weight) in [
+        (post.title(), TITLE_WEIGHT),
+        (post.description(), DESCRIPTION_WEIGHT),
+        (&strip_html(post.body()), BODY_WEIGHT),
+      ] {
+        for term in tokenize(lang, text) {
+          *frequencies.entry(term).or_default() += weight;
+          length += weight;
+        }
+      }
+
+      for (term, frequency) in frequencies {
+        language_index.terms.entry(term).or_default().push(Posting {
+          post: idx,
+          frequency,
+        });
+      }
+      language_index.lengths.insert(idx, length);
+    }
+
+    index
+  }
+
+  /// Returns the positions of all `visible` posts matching at least one term
+  /// of the query together with their score, best match first.
+  pub(crate) fn search(
+    &self,
+    lang: Language,
+    query: &str,
+    visible: impl Fn(usize) -> bool,
+  ) -> Vec<(usize, f32)> {
+    let Some(language_index) = self.languages.get(&lang) else {
+      return Vec::new();
+    };
+
+    let lengths = language_index
+      .lengths
+      .iter()
+      .filter(|(post, _)| visible(**post))
+      .map(|(_, length)| *length)
+      .collect::<Vec<_>>();
+    let documents = lengths.len() as f32;
+    let average_length = lengths.iter().sum::<f32>() / documents.max(1.0);
+
+    let mut scores: HashMap<usize, f32> = HashMap::new();
+
+    let mut terms = tokenize(lang, query).collect::<Vec<_>>();
+    terms.sort();
+    terms.dedup();
+
+    for term in terms {
+      let Some(postings) = language_index.terms.get(&term) else {
+        continue;
+      };
+      let postings = postings
+        .iter()
+        .filter(|posting| visible(posting.post))
+        .collect::<Vec<_>>();
+
+      let matching = postings.len() as f32;
+      let idf = (1.0 + (documents - matching + 0.5) / (matching + 0.5)).ln();
+
+      for posting in postings {
+        let length = language_index.lengths[&posting.post];
+        let norm = K1 * (1.0 - B + B * length / average_length);
+        *scores.entry(posting.post).or_default() +=
+          idf * posting.frequency * (K1 + 1.0) / (posting.frequency + norm);
+      }
+    }
+
+    let mut scores = scores.into_iter().collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+  }
+}
+
+/// Languages without a stemmer, e.g. Czech or Polish, are only matched on
+/// whole words.
+fn stemmer(lang: Language) -> Option<Stemmer> {
+  let algorithm = match lang.code() {
+    "ar" => Algorithm::Arabic,
+    "da" => Algorithm::Danish,
+    "de" => Algorithm::German,
+    "el" => Algorithm::Greek,
+    "en" => Algorithm::English,
+    "es" => Algorithm::Spanish,
+    "fi" => Algorithm::Finnish,
+    "fr" => Algorithm::French,
+    "hu" => Algorithm::Hungarian,
+    "it" => Algorithm::Italian,
+    "nl" => Algorithm::Dutch,
+    "no" => Algorithm::Norwegian,
+    "pt" => Algorithm::Portuguese,
+    "ro" => Algorithm::Romanian,
+    "ru" => Algorithm::Russian,
+    "sv" => Algorithm::Swedish,
+    "ta" => Algorithm::Tamil,
+    "tr" => Algorithm::Turkish,
+    _ => return None,
+  };
+
+  Some(Stemmer::create(algorithm))
+}
+
+fn tokenize(lang: Language, text: &str) -> impl Iterator<Item = String> + '_ {
+  let stemmer = stemmer(lang);
+
+  text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .map(move |word| {
+      let word = word.to_lowercase();
+      match &stemmer {
+        Some(stemmer) => stemmer.stem(&word).into_owned(),
+        None => word,
+      }
+    })
+}
+
+fn strip_html(html: &str) -> String {
+  HTML_TAG
+    .replace_all(html, " ")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&amp;", "&")
+}
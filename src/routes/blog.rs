@@ -2,12 +2,20 @@ use std::collections::HashSet;
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
 use axum::http::StatusCode;
+use axum::response::IntoResponse;
 use axum::Json;
 use serde::{Deserialize, Deserializer};
+use tracing::error;
 
 use crate::blog::{BlogPost, SmallBlogPost};
-use crate::lang::Language;
+use crate::conditional::Conditional;
+use crate::lang::{Language, Localized};
+use crate::posts::feed::Feed;
+use crate::posts::post_provider::PostMeta;
+use crate::routes::language::RequestLanguage;
+use crate::routes::pagination::{Listing, PageQuery};
 use crate::state::FoundationState;
 
 #[derive(Deserialize)]
@@ -33,27 +41,82 @@ where
 
 pub(crate) async fn list_posts(
   State(state): State<FoundationState>,
-  Path(lang): Path<Language>,
+  RequestLanguage(lang): RequestLanguage,
   Query(query): Query<ListQuery>,
-) -> Json<Vec<Arc<SmallBlogPost>>> {
-  match query.keywords {
-    None => Json(state.blog.content_by_lang(lang).await),
-    Some(keywords) => Json(state.blog.search_by_keywords(lang, &keywords.0).await),
-  }
+  Query(page): Query<PageQuery>,
+) -> Conditional<Localized<Json<Listing<SmallBlogPost>>>> {
+  let posts = match query.keywords {
+    None => state.blog.content_by_lang(lang).await,
+    Some(keywords) => state.blog.search_by_keywords(lang, &keywords.0).await,
+  };
+
+  let validators = state.blog.validators(lang, &posts).await;
+  Conditional::new(validators, Localized::new(lang, Json(page.apply(posts))))
 }
 
 pub(crate) async fn find_post(
   State(state): State<FoundationState>,
   Path((lang, slug)): Path<(Language, String)>,
-) -> Result<Json<Arc<BlogPost>>, StatusCode> {
-  state
+) -> Result<Conditional<Localized<Json<Arc<BlogPost>>>>, StatusCode> {
+  let post = state
     .blog
-    .content_by_slug(lang, &slug)
+    .translation_by_slug(&state.languages.chain(lang), &slug)
     .await
-    .map(Json)
-    .ok_or(StatusCode::NOT_FOUND)
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+  let validators = state.blog.post_validators(&post).await;
+  Ok(Conditional::new(
+    validators,
+    Localized::new(post.lang(), Json(post)),
+  ))
 }
 
 pub(crate) async fn find_keywords(State(state): State<FoundationState>) -> Json<HashSet<String>> {
   Json(state.blog.keywords().await)
 }
+
+async fn feed(state: &FoundationState, lang: Language, query: ListQuery) -> Feed<'_, BlogPost> {
+  let posts = match query.keywords {
+    None => state.blog.content_by_lang(lang).await,
+    Some(keywords) => state.blog.search_by_keywords(lang, &keywords.0).await,
+  };
+
+  Feed {
+    title: "DD-IX Blog",
+    section: "blog",
+    lang,
+    base_url: &state.base_url,
+    posts: state.blog.long_posts(&posts).await,
+  }
+}
+
+pub(crate) async fn rss_feed(
+  State(state): State<FoundationState>,
+  Path(lang): Path<Language>,
+  Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+  match feed(&state, lang, query).await.rss() {
+    Ok(rss) => Ok(([(CONTENT_TYPE, "application/rss+xml; charset=utf-8")], rss)),
+    Err(err) => {
+      error!("Error while building rss feed: {:?}", err);
+      Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+  }
+}
+
+pub(crate) async fn atom_feed(
+  State(state): State<FoundationState>,
+  Path(lang): Path<Language>,
+  Query(query): Query<ListQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+  match feed(&state, lang, query).await.atom() {
+    Ok(atom) => Ok((
+      [(CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+      atom,
+    )),
+    Err(err) => {
+      error!("Error while building atom feed: {:?}", err);
+      Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+  }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::http::header::CACHE_CONTROL;
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// How long browsers and proxies may cache the responses of each route.
+/// Upstream data sets its own `Cache-Control` based on its next refresh.
+#[derive(Clone)]
+pub(crate) struct CachePolicy {
+  pub(crate) assets: Duration,
+  pub(crate) content: Duration,
+}
+
+/// Routes answering per client or with internal data, never cached.
+const UNCACHED: [&str; 6] = [
+  "/preview/",
+  "/community/",
+  "/healthz",
+  "/readyz",
+  "/status",
+  "/metrics",
+];
+
+impl CachePolicy {
+  fn directive(&self, path: &str) -> HeaderValue {
+    if UNCACHED.iter().any(|prefix| path.starts_with(prefix)) {
+      HeaderValue::from_static("no-store")
+    } else if path.contains("/assets/") || path.starts_with("/documents/download/") {
+      max_age(self.assets)
+    } else {
+      max_age(self.content)
+    }
+  }
+}
+
+pub(super) fn max_age(max_age: Duration) -> HeaderValue {
+  HeaderValue::try_from(format!("public, max-age={}", max_age.as_secs()))
+    .expect("max-age is a valid header value")
+}
+
+pub(super) async fn cache_control(
+  State(policy): State<CachePolicy>,
+  request: Request,
+  next: Next,
+) -> Response {
+  let directive = policy.directive(request.uri().path());
+  let mut response = next.run(request).await;
+
+  // errors like a cache that is still being built should be retried soon
+  let cacheable = response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+  if directive == "no-store" || (cacheable && !response.headers().contains_key(CACHE_CONTROL)) {
+    response.headers_mut().insert(CACHE_CONTROL, directive);
+  }
+  response
+}
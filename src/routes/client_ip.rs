@@ -0,0 +1,264 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::FORWARDED;
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use ipnet::IpNet;
+
+use crate::config::ForwardedHeader;
+use crate::state::FoundationState;
+
+/// Reverse proxies in front of the api and the header they name the client
+/// in.
+pub(crate) struct TrustedProxies {
+  pub(crate) networks: Vec<IpNet>,
+  pub(crate) header: ForwardedHeader,
+}
+
+/// Address of the client, taken from the forwarding header as far as the
+/// proxies that set it are trusted, otherwise the peer of the connection.
+pub(crate) struct ClientIp(pub(crate) IpAddr);
+
+impl FromRequestParts<FoundationState> for ClientIp {
+  type Rejection = StatusCode;
+
+  async fn from_request_parts(
+    parts: &mut Parts,
+    state: &FoundationState,
+  ) -> Result<Self, Self::Rejection> {
+    let ConnectInfo(peer) = parts
+      .extensions
+      .get::<ConnectInfo<SocketAddr>>()
+      .copied()
+      .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(ClientIp(
+      state.trusted_proxies.resolve(peer.ip(), &parts.headers),
+    ))
+  }
+}
+
+impl TrustedProxies {
+  fn is_trusted(&self, addr: &IpAddr) -> bool {
+    self.networks.iter().any(|network| network.contains(addr))
+  }
+
+  /// Walks the chain of proxies from the peer towards the client and stops at
+  /// the first address that is not a trusted proxy, as everything to its left
+  /// may have been made up by the client.
+  fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+    let peer = peer.to_canonical();
+    if !self.is_trusted(&peer) {
+      return peer;
+    }
+
+    // only the header the proxies write is read, a client can send the other
+    // one through them unchanged
+    let hops = match self.header {
+      ForwardedHeader::XForwardedFor => x_forwarded_for(headers),
+      ForwardedHeader::Forwarded => forwarded_for(headers),
+    };
+
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+      // an unknown or obfuscated hop cannot be followed any further
+      let Some(addr) = hop else {
+        break;
+      };
+      client = *addr;
+      if !self.is_trusted(addr) {
+        break;
+      }
+    }
+    client
+  }
+}
+
+/// Addresses of all `X-Forwarded-For` headers, from the client to the last
+/// proxy.
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+  headers
+    .get_all("x-forwarded-for")
+    .iter()
+    .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+    .map(|hop| hop.trim().parse::<IpAddr>().ok())
+    .map(|addr| addr.map(|addr| addr.to_canonical()))
+    .collect()
+}
+
+/// `for` parameters of all `Forwarded` elements, e.g.
+/// `for=192.0.2.60;proto=http, for="[2001:db8::1]:4711"`. Elements without
+/// one yield `None`, so that the position of later hops is kept.
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+  headers
+    .get_all(FORWARDED)
+    .iter()
+    .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+    .map(|element| {
+      element.split(';').find_map(|pair| {
+        let (name, value) = pair.trim().split_once('=')?;
+        name
+          .eq_ignore_ascii_case("for")
+          .then(|| parse_node(value.trim_matches('"')))
+      })?
+    })
+    .collect()
+}
+
+/// Node of a `Forwarded` header, an address with an optional port, or
+/// `unknown` and obfuscated identifiers, which yield `None`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+  let addr = match node.strip_prefix('[') {
+    Some(ipv6) => ipv6.split_once(']')?.0.parse::<IpAddr>().ok()?,
+    None => node.split(':').next()?.parse::<IpAddr>().ok()?,
+  };
+  Some(addr.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::http::HeaderValue;
+
+  use super::*;
+
+  fn proxies(header: ForwardedHeader) -> TrustedProxies {
+    TrustedProxies {
+      networks: vec![
+        "127.0.0.0/8".parse().unwrap(),
+        "10.0.0.0/8".parse().unwrap(),
+      ],
+      header,
+    }
+  }
+
+  fn headers(lines: &[(&'static str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in lines {
+      headers.append(*name, HeaderValue::from_str(value).unwrap());
+    }
+    headers
+  }
+
+  fn ip(addr: &str) -> IpAddr {
+    addr.parse().unwrap()
+  }
+
+  #[test]
+  fn untrusted_peer_ignores_forged_headers() {
+    let headers = headers(&[
+      ("x-forwarded-for", "192.0.2.1"),
+      ("forwarded", "for=192.0.2.1"),
+    ]);
+    for header in [ForwardedHeader::XForwardedFor, ForwardedHeader::Forwarded] {
+      assert_eq!(
+        proxies(header).resolve(ip("198.51.100.7"), &headers),
+        ip("198.51.100.7")
+      );
+    }
+  }
+
+  #[test]
+  fn forged_left_most_x_forwarded_for_is_skipped() {
+    let headers = headers(&[("x-forwarded-for", "192.0.2.1, 198.51.100.7")]);
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("127.0.0.1"), &headers),
+      ip("198.51.100.7")
+    );
+  }
+
+  #[test]
+  fn trusted_hops_are_walked() {
+    let headers = headers(&[("x-forwarded-for", "192.0.2.1, 198.51.100.7, 10.0.0.2")]);
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("127.0.0.1"), &headers),
+      ip("198.51.100.7")
+    );
+  }
+
+  #[test]
+  fn client_supplied_forwarded_is_ignored() {
+    let headers = headers(&[
+      ("forwarded", "for=192.0.2.1"),
+      ("x-forwarded-for", "198.51.100.7"),
+    ]);
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("127.0.0.1"), &headers),
+      ip("198.51.100.7")
+    );
+  }
+
+  #[test]
+  fn forwarded_ipv6_node_with_port() {
+    let headers = headers(&[("forwarded", "for=\"[2001:db8::1]:4711\";proto=https")]);
+    assert_eq!(
+      proxies(ForwardedHeader::Forwarded).resolve(ip("127.0.0.1"), &headers),
+      ip("2001:db8::1")
+    );
+  }
+
+  #[test]
+  fn forwarded_unknown_and_obfuscated_nodes_stop_the_walk() {
+    for node in ["unknown", "_hidden"] {
+      let headers = headers(&[
+        ("forwarded", "for=192.0.2.1"),
+        ("forwarded", &format!("for={}", node)),
+      ]);
+      assert_eq!(
+        proxies(ForwardedHeader::Forwarded).resolve(ip("127.0.0.1"), &headers),
+        ip("127.0.0.1")
+      );
+    }
+  }
+
+  #[test]
+  fn forwarded_element_without_for_stops_the_walk() {
+    let headers = headers(&[("forwarded", "for=192.0.2.1, proto=https, for=10.0.0.2")]);
+    assert_eq!(
+      proxies(ForwardedHeader::Forwarded).resolve(ip("127.0.0.1"), &headers),
+      ip("10.0.0.2")
+    );
+  }
+
+  #[test]
+  fn multiple_header_lines_are_joined() {
+    let x_forwarded_for = headers(&[
+      ("x-forwarded-for", "192.0.2.1"),
+      ("x-forwarded-for", "198.51.100.7, 10.0.0.2"),
+    ]);
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("127.0.0.1"), &x_forwarded_for),
+      ip("198.51.100.7")
+    );
+
+    let forwarded = headers(&[
+      ("forwarded", "for=192.0.2.1"),
+      ("forwarded", "for=198.51.100.7"),
+    ]);
+    assert_eq!(
+      proxies(ForwardedHeader::Forwarded).resolve(ip("127.0.0.1"), &forwarded),
+      ip("198.51.100.7")
+    );
+  }
+
+  #[test]
+  fn ipv4_mapped_peers_are_canonical() {
+    let headers = headers(&[("x-forwarded-for", "::ffff:198.51.100.7")]);
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("::ffff:127.0.0.1"), &headers),
+      ip("198.51.100.7")
+    );
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("::ffff:198.51.100.7"), &HeaderMap::new()),
+      ip("198.51.100.7")
+    );
+  }
+
+  #[test]
+  fn missing_header_falls_back_to_the_peer() {
+    assert_eq!(
+      proxies(ForwardedHeader::XForwardedFor).resolve(ip("127.0.0.1"), &HeaderMap::new()),
+      ip("127.0.0.1")
+    );
+  }
+}
@@ -0,0 +1,114 @@
+use std::time::SystemTime;
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::header::{
+  CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+  LAST_MODIFIED,
+};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use super::cache_control;
+use crate::cache::Cached;
+use crate::conditional::{Conditional, Validators};
+
+/// Set on values restored from the state directory and not refreshed since,
+/// the `Warning` header used for this before is obsolete.
+static X_CACHE_STALE: HeaderName = HeaderName::from_static("x-cache-stale");
+
+impl Validators {
+  fn apply(&self, headers: &mut HeaderMap) {
+    if let Ok(etag) = HeaderValue::try_from(self.etag.to_string()) {
+      headers.insert(ETAG, etag);
+    }
+    if let Some(last_modified) = self.last_modified {
+      let date = httpdate::fmt_http_date(SystemTime::from(last_modified));
+      if let Ok(date) = HeaderValue::try_from(date) {
+        headers.insert(LAST_MODIFIED, date);
+      }
+    }
+  }
+}
+
+impl<T: IntoResponse> IntoResponse for Conditional<T> {
+  fn into_response(self) -> Response {
+    let mut response = self.value.into_response();
+    if response.status().is_success() {
+      self.validators.apply(response.headers_mut());
+    }
+    response
+  }
+}
+
+impl<T: IntoResponse> IntoResponse for Cached<T> {
+  fn into_response(self) -> Response {
+    let mut response = Conditional::new(self.validators, self.value).into_response();
+    if response.status().is_success() {
+      // stale values are refreshed in the background right away
+      response
+        .headers_mut()
+        .insert(CACHE_CONTROL, cache_control::max_age(self.max_age));
+    }
+    if self.stale {
+      response
+        .headers_mut()
+        .insert(X_CACHE_STALE.clone(), HeaderValue::from_static("true"));
+    }
+    response
+  }
+}
+
+/// Answers conditional `GET` and `HEAD` requests with `304 Not Modified` if
+/// the validators of the response match the ones the client already has.
+pub(super) async fn not_modified(request: Request, next: Next) -> Response {
+  let conditional = matches!(*request.method(), Method::GET | Method::HEAD);
+  let if_none_match = request.headers().get(IF_NONE_MATCH).cloned();
+  let if_modified_since = request.headers().get(IF_MODIFIED_SINCE).cloned();
+
+  let response = next.run(request).await;
+  if !conditional || response.status() != StatusCode::OK {
+    return response;
+  }
+
+  // If-Modified-Since is ignored if the client sent an entity tag
+  let fresh = match if_none_match {
+    Some(if_none_match) => etag_matches(&if_none_match, response.headers().get(ETAG)),
+    None => if_modified_since.is_some_and(|if_modified_since| {
+      unmodified_since(&if_modified_since, response.headers().get(LAST_MODIFIED))
+    }),
+  };
+  if !fresh {
+    return response;
+  }
+
+  let (mut parts, _) = response.into_parts();
+  parts.status = StatusCode::NOT_MODIFIED;
+  parts.headers.remove(CONTENT_TYPE);
+  parts.headers.remove(CONTENT_LENGTH);
+  Response::from_parts(parts, Body::empty())
+}
+
+/// Weak comparison of the `If-None-Match` list against the entity tag of the
+/// response.
+fn etag_matches(if_none_match: &HeaderValue, etag: Option<&HeaderValue>) -> bool {
+  let (Ok(if_none_match), Some(Ok(etag))) = (if_none_match.to_str(), etag.map(HeaderValue::to_str))
+  else {
+    return false;
+  };
+
+  let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+  let etag = opaque(etag);
+  if_none_match
+    .split(',')
+    .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+fn unmodified_since(if_modified_since: &HeaderValue, last_modified: Option<&HeaderValue>) -> bool {
+  let parse = |value: &HeaderValue| httpdate::parse_http_date(value.to_str().ok()?).ok();
+  match (parse(if_modified_since), last_modified.and_then(parse)) {
+    (Some(since), Some(last_modified)) => last_modified <= since,
+    _ => false,
+  }
+}
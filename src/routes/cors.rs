@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::extract::{Request, State};
+use axum::http::header::{
+  ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+  ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD, ALLOW,
+  ORIGIN, VARY,
+};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::config::CorsConfig;
+
+/// Marks the request a preflight sends through the router to read the
+/// allowed methods, it is not a request of the client and not tracked.
+#[derive(Clone, Copy)]
+pub(crate) struct PreflightProbe;
+
+#[derive(Clone)]
+enum OriginPattern {
+  Any,
+  Exact(String),
+  /// `https://*.example.com`, matching every subdomain but not the domain
+  /// itself.
+  Subdomains {
+    scheme: String,
+    suffix: String,
+  },
+}
+
+impl OriginPattern {
+  fn parse(pattern: &str) -> anyhow::Result<Self> {
+    if pattern == "*" {
+      return Ok(Self::Any);
+    }
+
+    let (scheme, host) = pattern
+      .split_once("://")
+      .ok_or_else(|| anyhow!("CORS origin {} has no scheme", pattern))?;
+
+    match host.trim_end_matches('/').strip_prefix('*') {
+      Some(suffix) if suffix.starts_with('.') && !suffix.contains('*') => Ok(Self::Subdomains {
+        scheme: format!("{}://", scheme),
+        suffix: suffix.to_string(),
+      }),
+      None if !host.contains('*') => Ok(Self::Exact(pattern.trim_end_matches('/').to_string())),
+      _ => Err(anyhow!(
+        "CORS origin {} may only have a wildcard as its first label",
+        pattern
+      )),
+    }
+  }
+
+  fn matches(&self, origin: &str) -> bool {
+    match self {
+      Self::Any => true,
+      Self::Exact(exact) => exact == origin,
+      Self::Subdomains { scheme, suffix } => origin
+        .strip_prefix(scheme.as_str())
+        .and_then(|host| host.strip_suffix(suffix.as_str()))
+        .is_some_and(|subdomain| !subdomain.is_empty() && !subdomain.contains(['/', ':', '@'])),
+    }
+  }
+}
+
+/// Origins allowed to call the api. The methods allowed in a preflight are
+/// the ones the router accepts for the requested path, so the policy has to
+/// wrap the whole router instead of being layered onto its routes.
+#[derive(Clone)]
+pub(crate) struct CorsPolicy {
+  origins: Vec<OriginPattern>,
+  headers: HeaderValue,
+  credentials: bool,
+  max_age: Duration,
+}
+
+impl CorsPolicy {
+  pub(crate) fn new(config: &CorsConfig) -> anyhow::Result<Self> {
+    let origins = config
+      .origins
+      .iter()
+      .map(|origin| OriginPattern::parse(origin))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if config.credentials
+      && origins
+        .iter()
+        .any(|origin| matches!(origin, OriginPattern::Any))
+    {
+      return Err(anyhow!(
+        "CORS credentials cannot be allowed for every origin"
+      ));
+    }
+
+    Ok(Self {
+      origins,
+      headers: HeaderValue::try_from(config.headers.join(", "))?,
+      credentials: config.credentials,
+      max_age: config.max_age,
+    })
+  }
+
+  /// Value of `Access-Control-Allow-Origin` for a request from `origin`.
+  fn allow_origin(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+    let origin_str = origin.to_str().ok()?;
+    let pattern = self
+      .origins
+      .iter()
+      .find(|pattern| pattern.matches(origin_str))?;
+
+    // the wildcard cannot be combined with credentials, so echo the origin
+    Some(match pattern {
+      OriginPattern::Any if !self.credentials => HeaderValue::from_static("*"),
+      _ => origin.clone(),
+    })
+  }
+
+  fn apply(&self, allow_origin: HeaderValue, headers: &mut HeaderMap) {
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+    if self.credentials {
+      headers.insert(
+        ACCESS_CONTROL_ALLOW_CREDENTIALS,
+        HeaderValue::from_static("true"),
+      );
+    }
+  }
+}
+
+pub(crate) async fn cors(
+  State(policy): State<CorsPolicy>,
+  request: Request,
+  next: Next,
+) -> Response {
+  let allow_origin = request
+    .headers()
+    .get(ORIGIN)
+    .and_then(|origin| policy.allow_origin(origin));
+
+  let preflight = request.method() == Method::OPTIONS
+    && request
+      .headers()
+      .contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+  if preflight {
+    return preflight_response(&policy, allow_origin, request, next).await;
+  }
+
+  let mut response = next.run(request).await;
+  response
+    .headers_mut()
+    .append(VARY, HeaderValue::from_static("origin"));
+  if let Some(allow_origin) = allow_origin {
+    policy.apply(allow_origin, response.headers_mut());
+  }
+  response
+}
+
+/// Answers a preflight with the methods of the route, which the router lists
+/// in the `Allow` header when it rejects the `OPTIONS` request itself.
+async fn preflight_response(
+  policy: &CorsPolicy,
+  allow_origin: Option<HeaderValue>,
+  mut request: Request,
+  next: Next,
+) -> Response {
+  let requested = request
+    .headers()
+    .get(ACCESS_CONTROL_REQUEST_METHOD)
+    .and_then(|method| method.to_str().ok())
+    .map(str::to_string);
+
+  request.extensions_mut().insert(PreflightProbe);
+  let route = next.run(request).await;
+  if route.status() == StatusCode::NOT_FOUND {
+    return route;
+  }
+
+  let methods = route.headers().get(ALLOW).cloned();
+  let method_allowed = methods
+    .as_ref()
+    .and_then(|methods| methods.to_str().ok())
+    .zip(requested)
+    .is_some_and(|(methods, requested)| {
+      methods.split(',').any(|method| method.trim() == requested)
+    });
+
+  let mut response = StatusCode::NO_CONTENT.into_response();
+  response.headers_mut().insert(
+    VARY,
+    HeaderValue::from_static("origin, access-control-request-method"),
+  );
+
+  let (Some(allow_origin), Some(methods), true) = (allow_origin, methods, method_allowed) else {
+    *response.status_mut() = StatusCode::FORBIDDEN;
+    return response;
+  };
+
+  let headers = response.headers_mut();
+  policy.apply(allow_origin, headers);
+  headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+  headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, policy.headers.clone());
+  headers.insert(
+    ACCESS_CONTROL_MAX_AGE,
+    HeaderValue::from(policy.max_age.as_secs()),
+  );
+  response
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn matches(pattern: &str, origin: &str) -> bool {
+    OriginPattern::parse(pattern).unwrap().matches(origin)
+  }
+
+  #[test]
+  fn subdomains_match() {
+    assert!(matches("https://*.dd-ix.net", "https://x.dd-ix.net"));
+    assert!(matches("https://*.dd-ix.net", "https://a.b.dd-ix.net"));
+  }
+
+  #[test]
+  fn subdomains_reject_other_domains() {
+    assert!(!matches(
+      "https://*.dd-ix.net",
+      "https://x.dd-ix.net.evil.com"
+    ));
+    assert!(!matches("https://*.dd-ix.net", "https://evil-dd-ix.net"));
+    assert!(!matches(
+      "https://*.dd-ix.net",
+      "https://evil.com/.dd-ix.net"
+    ));
+    assert!(!matches(
+      "https://*.dd-ix.net",
+      "https://evil.com@x.dd-ix.net"
+    ));
+  }
+
+  #[test]
+  fn subdomains_reject_the_domain_itself() {
+    assert!(!matches("https://*.dd-ix.net", "https://dd-ix.net"));
+    assert!(!matches("https://*.dd-ix.net", "https://.dd-ix.net"));
+  }
+
+  #[test]
+  fn port_and_scheme_must_match() {
+    assert!(!matches("https://*.dd-ix.net", "https://x.dd-ix.net:8443"));
+    assert!(!matches("https://*.dd-ix.net", "http://x.dd-ix.net"));
+    assert!(!matches("https://dd-ix.net", "https://dd-ix.net:8443"));
+    assert!(!matches("https://dd-ix.net", "http://dd-ix.net"));
+    assert!(matches("http://localhost:4200", "http://localhost:4200"));
+    assert!(!matches("http://localhost:4200", "http://localhost:4201"));
+  }
+
+  #[test]
+  fn trailing_slashes() {
+    assert!(matches("https://dd-ix.net/", "https://dd-ix.net"));
+    assert!(matches("https://*.dd-ix.net/", "https://x.dd-ix.net"));
+    // browsers never send one, an origin with a path is not an origin
+    assert!(!matches("https://dd-ix.net", "https://dd-ix.net/"));
+    assert!(!matches("https://*.dd-ix.net", "https://x.dd-ix.net/"));
+  }
+
+  #[test]
+  fn null_origin() {
+    assert!(!matches("https://dd-ix.net", "null"));
+    assert!(!matches("https://*.dd-ix.net", "null"));
+    assert!(matches("*", "null"));
+  }
+
+  #[test]
+  fn invalid_patterns() {
+    assert!(OriginPattern::parse("dd-ix.net").is_err());
+    assert!(OriginPattern::parse("https://x.*.dd-ix.net").is_err());
+    assert!(OriginPattern::parse("https://*dd-ix.net").is_err());
+  }
+}
@@ -1,15 +1,22 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::State;
+use axum::http::StatusCode;
 use axum::Json;
 
 use crate::documents::Document;
-use crate::lang::Language;
+use crate::lang::Localized;
+use crate::routes::language::RequestLanguage;
 use crate::state::FoundationState;
 
 pub(crate) async fn list_documents(
   State(state): State<FoundationState>,
-  Path(lang): Path<Language>,
-) -> Json<Arc<Vec<Document>>> {
-  Json(state.documents.documents(lang).unwrap())
+  RequestLanguage(lang): RequestLanguage,
+) -> Result<Localized<Json<Arc<Vec<Document>>>>, StatusCode> {
+  state
+    .documents
+    .documents(&state.languages.chain(lang))
+    .await
+    .map(|documents| documents.map(Json))
+    .ok_or(StatusCode::NOT_FOUND)
 }
@@ -1,40 +1,101 @@
 use std::sync::Arc;
 
-use crate::event::{EventPost, SmallEventPost};
+use crate::event::{calendar, EventPost, SmallEventPost};
 use crate::posts::post_provider::PostMeta;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use time::OffsetDateTime;
+use tracing::error;
 
-use crate::lang::Language;
+use crate::conditional::Conditional;
+use crate::lang::{Language, Localized};
+use crate::routes::language::RequestLanguage;
+use crate::routes::pagination::{Listing, PageQuery};
 use crate::state::FoundationState;
 
 pub(crate) async fn list_all_events(
   State(state): State<FoundationState>,
-  Path(lang): Path<Language>,
-) -> Json<Vec<Arc<SmallEventPost>>> {
-  Json(state.events.content_by_lang(lang).await)
+  RequestLanguage(lang): RequestLanguage,
+  Query(page): Query<PageQuery>,
+) -> Conditional<Localized<Json<Listing<SmallEventPost>>>> {
+  let events = state.events.content_by_lang(lang).await;
+  let validators = state.events.validators(lang, &events).await;
+  Conditional::new(validators, Localized::new(lang, Json(page.apply(events))))
 }
 
 pub(crate) async fn list_future_events(
+  State(state): State<FoundationState>,
+  RequestLanguage(lang): RequestLanguage,
+  Query(page): Query<PageQuery>,
+) -> Conditional<Localized<Json<Listing<SmallEventPost>>>> {
+  let events = future_events(&state, lang).await;
+  let validators = state.events.validators(lang, &events).await;
+  Conditional::new(validators, Localized::new(lang, Json(page.apply(events))))
+}
+
+pub(crate) async fn find_event(
+  State(state): State<FoundationState>,
+  Path((lang, slug)): Path<(Language, String)>,
+) -> Response {
+  let langs = state.languages.chain(lang);
+  match state.events.translation_by_slug(&langs, &slug).await {
+    Some(event) => {
+      let validators = state.events.post_validators(&event).await;
+      Conditional::new(validators, Localized::new(event.lang(), Json(Some(event)))).into_response()
+    }
+    None => Json(None::<Arc<EventPost>>).into_response(),
+  }
+}
+
+pub(crate) async fn event_calendar(
+  State(state): State<FoundationState>,
+  Path((lang, slug)): Path<(Language, String)>,
+) -> Response {
+  let langs = state.languages.chain(lang);
+  match state.events.translation_by_slug(&langs, &slug).await {
+    Some(event) => {
+      Localized::new(event.lang(), calendar_response(&state, &[event])).into_response()
+    }
+    None => StatusCode::NOT_FOUND.into_response(),
+  }
+}
+
+pub(crate) async fn all_events_calendar(
+  State(state): State<FoundationState>,
+  Path(lang): Path<Language>,
+) -> Response {
+  let events = state.events.content_by_lang(lang).await;
+  calendar_response(&state, &state.events.long_posts(&events).await)
+}
+
+pub(crate) async fn future_events_calendar(
   State(state): State<FoundationState>,
   Path(lang): Path<Language>,
-) -> Json<Vec<Arc<SmallEventPost>>> {
+) -> Response {
+  let events = future_events(&state, lang).await;
+  calendar_response(&state, &state.events.long_posts(&events).await)
+}
+
+async fn future_events(state: &FoundationState, lang: Language) -> Vec<Arc<SmallEventPost>> {
   let events: Vec<Arc<SmallEventPost>> = state.events.content_by_lang(lang).await;
   let current_date_time = OffsetDateTime::now_utc();
 
-  Json(
-    events
-      .iter()
-      .filter(|post| post.end_time > current_date_time && post.lang() == lang)
-      .cloned()
-      .collect(),
-  )
+  events
+    .iter()
+    .filter(|post| post.end_time > current_date_time && post.lang() == lang)
+    .cloned()
+    .collect()
 }
 
-pub(crate) async fn find_event(
-  State(state): State<FoundationState>,
-  Path((lang, slug)): Path<(Language, String)>,
-) -> Json<Option<Arc<EventPost>>> {
-  Json(state.events.content_by_slug(lang, &slug).await)
+fn calendar_response(state: &FoundationState, events: &[Arc<EventPost>]) -> Response {
+  match calendar(events, &state.base_url) {
+    Ok(calendar) => ([(CONTENT_TYPE, "text/calendar; charset=utf-8")], calendar).into_response(),
+    Err(err) => {
+      error!("Error while building calendar: {:?}", err);
+      StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    }
+  }
 }
@@ -0,0 +1,46 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+
+use crate::cache::CacheStatus;
+use crate::state::FoundationState;
+
+#[derive(Serialize)]
+pub(crate) struct Status {
+  healthy: bool,
+  ready: bool,
+  caches: Vec<CacheStatus>,
+}
+
+fn caches(state: &FoundationState) -> Vec<CacheStatus> {
+  let mut caches = state.stats.status();
+  caches.push(state.peers.status());
+  caches.push(state.looking_glass.routes.status());
+  caches.push(state.bird.status());
+  caches
+}
+
+/// Liveness, the process is up and serving requests.
+pub(crate) async fn healthz() -> &'static str {
+  "ok"
+}
+
+/// Readiness, every cache refreshed in the background holds a value.
+pub(crate) async fn readyz(State(state): State<FoundationState>) -> (StatusCode, &'static str) {
+  if caches(&state).iter().all(CacheStatus::is_ready) {
+    (StatusCode::OK, "ok")
+  } else {
+    (StatusCode::SERVICE_UNAVAILABLE, "still building caches")
+  }
+}
+
+pub(crate) async fn status(State(state): State<FoundationState>) -> Json<Status> {
+  let caches = caches(&state);
+
+  Json(Status {
+    healthy: caches.iter().all(CacheStatus::is_healthy),
+    ready: caches.iter().all(CacheStatus::is_ready),
+    caches,
+  })
+}
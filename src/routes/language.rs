@@ -0,0 +1,57 @@
+use axum::extract::{FromRequestParts, RawPathParams};
+use axum::http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, VARY};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::lang::{Language, Localized};
+use crate::state::FoundationState;
+
+/// Language taken from the `{lang}` path parameter or, on language-neutral
+/// routes, negotiated from the `Accept-Language` header.
+pub(crate) struct RequestLanguage(pub(crate) Language);
+
+impl FromRequestParts<FoundationState> for RequestLanguage {
+  type Rejection = Response;
+
+  async fn from_request_parts(
+    parts: &mut Parts,
+    state: &FoundationState,
+  ) -> Result<Self, Self::Rejection> {
+    let params = RawPathParams::from_request_parts(parts, state)
+      .await
+      .map_err(IntoResponse::into_response)?;
+
+    if let Some((_, lang)) = params.iter().find(|(name, _)| *name == "lang") {
+      return Language::try_from(lang)
+        .map(RequestLanguage)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()).into_response());
+    }
+
+    let accept_language = parts
+      .headers
+      .get(ACCEPT_LANGUAGE)
+      .and_then(|value| value.to_str().ok());
+
+    Ok(RequestLanguage(state.languages.negotiate(accept_language)))
+  }
+}
+
+impl<T: IntoResponse> IntoResponse for Localized<T> {
+  fn into_response(self) -> Response {
+    let langs = self
+      .langs
+      .iter()
+      .map(Language::code)
+      .collect::<Vec<_>>()
+      .join(", ");
+
+    let mut response = self.content.into_response();
+    let headers = response.headers_mut();
+    if let Ok(langs) = HeaderValue::from_str(&langs) {
+      headers.insert(CONTENT_LANGUAGE, langs);
+    }
+    headers.append(VARY, HeaderValue::from_static("accept-language"));
+    response
+  }
+}
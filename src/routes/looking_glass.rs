@@ -1,13 +1,15 @@
-use crate::looking_glass::is_address_in_network;
+use crate::cache::Cached;
+use crate::conditional::ETag;
+use crate::looking_glass::Announcement;
+use crate::routes::client_ip::ClientIp;
 use crate::state::FoundationState;
-use axum::extract::State;
-use axum::http::{HeaderMap, StatusCode};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
 use axum::Json;
-use serde::Serialize;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
-use std::str::FromStr;
-use std::sync::Arc;
-use tracing::info;
+use tracing::debug;
 
 #[derive(Serialize)]
 pub struct NetworkInformation {
@@ -15,26 +17,109 @@ pub struct NetworkInformation {
 }
 
 pub(crate) async fn get_connected_to_community(
-  headers: HeaderMap,
+  ClientIp(addr): ClientIp,
   State(state): State<FoundationState>,
-) -> Result<Json<Arc<NetworkInformation>>, StatusCode> {
-  let header_value = match headers.get("X-Forwarded-For") {
-    Some(header_value) => header_value,
-    None => return Err(StatusCode::BAD_REQUEST),
+) -> Result<Cached<Json<NetworkInformation>>, StatusCode> {
+  let routes = match state.looking_glass.routes.get_cached() {
+    Some(routes) => routes,
+    None => return Err(StatusCode::SERVICE_UNAVAILABLE),
   };
-  let addr = header_value
-    .to_str()
-    .map_err(|_| StatusCode::BAD_REQUEST)
-    .and_then(|string_value| IpAddr::from_str(string_value).map_err(|_| StatusCode::BAD_REQUEST))?;
 
-  let routes = match state.looking_glass.routes.get_cached().await {
+  debug!(%addr, networks = routes.value.len(), "Checking address");
+
+  // the answer depends on the address as well as on the routes
+  let mut connected = routes.map(|routes| {
+    Json(NetworkInformation {
+      is_connected: routes.longest_match(addr).is_some(),
+    })
+  });
+  connected.validators.etag = ETag::combine([connected.validators.etag, ETag::of(addr)]);
+  Ok(connected)
+}
+
+#[derive(Deserialize)]
+pub(super) struct LookupQuery {
+  ip: Option<IpAddr>,
+  prefix: Option<IpNet>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum AddressFamily {
+  Ipv4,
+  Ipv6,
+}
+
+impl From<IpNet> for AddressFamily {
+  fn from(network: IpNet) -> Self {
+    match network {
+      IpNet::V4(_) => Self::Ipv4,
+      IpNet::V6(_) => Self::Ipv6,
+    }
+  }
+}
+
+#[derive(Serialize)]
+pub(super) struct RoutedPrefix {
+  prefix: IpNet,
+  family: AddressFamily,
+  announced_by: Vec<Announcement>,
+}
+
+impl RoutedPrefix {
+  fn new((prefix, announced_by): (IpNet, &[Announcement])) -> Self {
+    Self {
+      prefix,
+      family: prefix.into(),
+      announced_by: announced_by.to_vec(),
+    }
+  }
+}
+
+#[derive(Serialize)]
+pub(super) struct Lookup {
+  query: IpNet,
+  family: AddressFamily,
+  /// Most specific prefix first, empty if the query is not routed via the
+  /// route servers.
+  prefixes: Vec<RoutedPrefix>,
+  /// Prefixes within the queried one, empty for addresses.
+  more_specifics: Vec<RoutedPrefix>,
+}
+
+/// Prefixes received by the route servers covering the address `ip` or the
+/// network `prefix`, with the neighbors announcing them.
+pub(super) async fn lookup(
+  Query(query): Query<LookupQuery>,
+  State(state): State<FoundationState>,
+) -> Result<Cached<Json<Lookup>>, StatusCode> {
+  let query = match (query.ip, query.prefix) {
+    (Some(ip), None) => IpNet::from(ip),
+    (None, Some(prefix)) => prefix.trunc(),
+    _ => return Err(StatusCode::BAD_REQUEST),
+  };
+
+  let routes = match state.looking_glass.routes.get_cached() {
     Some(routes) => routes,
     None => return Err(StatusCode::SERVICE_UNAVAILABLE),
   };
 
-  println!("addr is {} lengths: {}", addr, routes.len());
-
-  Ok(Json(Arc::new(NetworkInformation {
-    is_connected: is_address_in_network(&(routes), addr),
-  })))
+  let mut lookup = routes.map(|routes| {
+    Json(Lookup {
+      query,
+      family: query.into(),
+      prefixes: routes
+        .covering(query)
+        .into_iter()
+        .map(RoutedPrefix::new)
+        .collect(),
+      more_specifics: routes
+        .covered(query)
+        .into_iter()
+        .map(RoutedPrefix::new)
+        .collect(),
+    })
+  });
+  lookup.validators.etag = ETag::combine([lookup.validators.etag, ETag::of(query)]);
+  Ok(lookup)
 }
@@ -0,0 +1,26 @@
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use metrics::gauge;
+
+use crate::lang::Language;
+use crate::state::FoundationState;
+
+pub(crate) async fn metrics(State(state): State<FoundationState>) -> impl IntoResponse {
+  for (kind, counts) in [
+    ("blog", state.blog.count_by_lang().await),
+    ("news", state.news.count_by_lang().await),
+    ("event", state.events.count_by_lang().await),
+  ] {
+    // languages without posts are reset, their last post may have been removed
+    for lang in Language::all() {
+      let count = counts.get(lang).copied().unwrap_or_default();
+      gauge!("content_posts", "type" => kind, "lang" => lang.code()).set(count as f64);
+    }
+  }
+
+  (
+    [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+    state.metrics.render(),
+  )
+}
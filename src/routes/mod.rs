@@ -2,39 +2,60 @@ use std::path::PathBuf;
 
 use crate::routes::bird::get_bird;
 use crate::routes::mirrors::get_mirrors;
+use axum::middleware;
 use axum::routing::get;
 use axum::Router;
 use tower_http::services::ServeDir;
 
 use crate::routes::blog::{
-  find_keywords as blog_find_keywords, find_post as blog_find_post, list_posts as blog_list_posts,
+  atom_feed as blog_atom_feed, find_keywords as blog_find_keywords, find_post as blog_find_post,
+  list_posts as blog_list_posts, rss_feed as blog_rss_feed,
 };
 use crate::routes::documents::list_documents;
-use crate::routes::event::{find_event, list_all_events, list_future_events};
-use crate::routes::looking_glass::get_connected_to_community;
+use crate::routes::event::{
+  all_events_calendar, event_calendar, find_event, future_events_calendar, list_all_events,
+  list_future_events,
+};
+use crate::routes::looking_glass::{get_connected_to_community, lookup};
 use crate::routes::news::{
-  find_keywords as news_find_keywords, find_post as news_find_post, list_posts as news_list_posts,
+  atom_feed as news_atom_feed, find_keywords as news_find_keywords, find_post as news_find_post,
+  list_posts as news_list_posts, rss_feed as news_rss_feed,
 };
 use crate::routes::peers::get_peers_and_supporter;
+use crate::routes::search::search;
 use crate::routes::team::get_team;
 use crate::routes::text_blocks::find_text_block;
 use crate::state::FoundationState;
 
+pub(crate) use self::cache_control::CachePolicy;
+pub(crate) use self::client_ip::TrustedProxies;
+pub(crate) use self::cors::{cors, CorsPolicy, PreflightProbe};
+
 use self::stats::{get_as112_stats, get_traffic_stats};
 
 mod bird;
 mod blog;
+mod cache_control;
+mod client_ip;
+mod conditional;
+mod cors;
 mod documents;
 mod event;
+mod health;
+mod language;
 mod peers;
+mod search;
 mod stats;
 mod team;
 mod text_blocks;
 
 mod mirrors;
 mod news;
+mod pagination;
+mod preview;
 
 mod looking_glass;
+mod metrics;
 
 pub(crate) struct ContentPaths {
   pub(crate) blog: PathBuf,
@@ -45,17 +66,43 @@ pub(crate) struct ContentPaths {
   pub(crate) team: PathBuf,
 }
 
-pub(crate) fn route(content_paths: &ContentPaths) -> Router<FoundationState> {
-  Router::new()
+/// Routes of the api, `/metrics` is left out when it has a listener of its
+/// own.
+pub(crate) fn route(
+  content_paths: &ContentPaths,
+  cache_policy: CachePolicy,
+  serve_metrics: bool,
+) -> Router<FoundationState> {
+  let router = Router::new()
+    .route("/blog", get(blog_list_posts))
     .route("/blog/{lang}", get(blog_list_posts))
+    .route("/blog/{lang}/feed.xml", get(blog_rss_feed))
+    .route("/blog/{lang}/atom.xml", get(blog_atom_feed))
     .route("/blog/{lang}/{slug}", get(blog_find_post))
     .route("/blog/keywords", get(blog_find_keywords))
+    .route("/news", get(news_list_posts))
     .route("/news/{lang}", get(news_list_posts))
+    .route("/news/{lang}/feed.xml", get(news_rss_feed))
+    .route("/news/{lang}/atom.xml", get(news_atom_feed))
     .route("/news/{lang}/{slug}", get(news_find_post))
     .route("/news/keywords", get(news_find_keywords))
+    .route("/event/all", get(list_all_events))
+    .route("/event/upcoming", get(list_future_events))
     .route("/event/{lang}/all", get(list_all_events))
     .route("/event/{lang}/upcoming", get(list_future_events))
+    .route("/event/{lang}/all.ics", get(all_events_calendar))
+    .route("/event/{lang}/upcoming.ics", get(future_events_calendar))
     .route("/event/{lang}/{slug}", get(find_event))
+    .route("/event/{lang}/{slug}/event.ics", get(event_calendar))
+    .route("/search", get(search))
+    .route("/search/{lang}", get(search))
+    .route("/preview/blog/{lang}", get(preview::list_blog_posts))
+    .route("/preview/blog/{lang}/{slug}", get(preview::find_blog_post))
+    .route("/preview/news/{lang}", get(preview::list_news_posts))
+    .route("/preview/news/{lang}/{slug}", get(preview::find_news_post))
+    .route("/preview/event/{lang}", get(preview::list_events))
+    .route("/preview/event/{lang}/{slug}", get(preview::find_event))
+    .route("/text-blocks/{slug}", get(find_text_block))
     .route("/text-blocks/{lang}/{slug}", get(find_text_block))
     .nest_service(
       "/text-blocks/assets",
@@ -64,11 +111,13 @@ pub(crate) fn route(content_paths: &ContentPaths) -> Router<FoundationState> {
     .nest_service("/blog/assets", ServeDir::new(&content_paths.blog))
     .nest_service("/news/assets", ServeDir::new(&content_paths.news))
     .nest_service("/event/assets", ServeDir::new(&content_paths.event))
+    .route("/documents", get(list_documents))
     .route("/documents/{lang}", get(list_documents))
     .nest_service(
       "/documents/download",
       ServeDir::new(&content_paths.document),
     )
+    .route("/team", get(get_team))
     .route("/team/{lang}", get(get_team))
     .nest_service("/team/assets", ServeDir::new(&content_paths.team))
     .route("/stats/traffic/{selection}", get(get_traffic_stats))
@@ -77,4 +126,26 @@ pub(crate) fn route(content_paths: &ContentPaths) -> Router<FoundationState> {
     .route("/bird", get(get_bird))
     .route("/mirrors", get(get_mirrors))
     .route("/community/connected", get(get_connected_to_community))
+    .route("/looking-glass/lookup", get(lookup))
+    .route("/healthz", get(health::healthz))
+    .route("/readyz", get(health::readyz))
+    .route("/status", get(health::status));
+
+  let router = if serve_metrics {
+    router.merge(metrics_route())
+  } else {
+    router
+  };
+
+  router
+    .layer(middleware::from_fn(conditional::not_modified))
+    .layer(middleware::from_fn_with_state(
+      cache_policy,
+      cache_control::cache_control,
+    ))
+}
+
+/// Routes of the separate metrics listener.
+pub(crate) fn metrics_route() -> Router<FoundationState> {
+  Router::new().route("/metrics", get(metrics::metrics))
 }
@@ -0,0 +1,80 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::posts::post_provider::PostMeta;
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SortBy {
+  #[default]
+  Idx,
+  /// publish date of posts, start of events
+  #[serde(alias = "published", alias = "start")]
+  Date,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SortOrder {
+  Asc,
+  #[default]
+  Desc,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PageQuery {
+  limit: Option<usize>,
+  offset: Option<usize>,
+  #[serde(default)]
+  sort: SortBy,
+  #[serde(default)]
+  order: SortOrder,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Page<T> {
+  total: usize,
+  offset: usize,
+  limit: Option<usize>,
+  items: Vec<Arc<T>>,
+}
+
+/// Without `limit` or `offset` the whole list is returned as before, otherwise
+/// the requested page wrapped in an envelope carrying the total count.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Listing<T> {
+  All(Vec<Arc<T>>),
+  Page(Page<T>),
+}
+
+impl PageQuery {
+  pub(crate) fn apply<T: PostMeta>(&self, mut items: Vec<Arc<T>>) -> Listing<T> {
+    match (self.sort, self.order) {
+      (SortBy::Idx, SortOrder::Asc) => items.sort_by_key(|item| item.idx()),
+      (SortBy::Idx, SortOrder::Desc) => items.sort_by_key(|item| Reverse(item.idx())),
+      (SortBy::Date, SortOrder::Asc) => items.sort_by_key(|item| item.date()),
+      (SortBy::Date, SortOrder::Desc) => items.sort_by_key(|item| Reverse(item.date())),
+    }
+
+    if self.limit.is_none() && self.offset.is_none() {
+      return Listing::All(items);
+    }
+
+    let total = items.len();
+    let offset = self.offset.unwrap_or(0);
+
+    Listing::Page(Page {
+      total,
+      offset,
+      limit: self.limit,
+      items: items
+        .into_iter()
+        .skip(offset)
+        .take(self.limit.unwrap_or(usize::MAX))
+        .collect(),
+    })
+  }
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::cache::Cached;
 use crate::peers::FoundationEntity;
 use axum::extract::State;
 use axum::http::StatusCode;
@@ -10,9 +11,9 @@ use crate::state::FoundationState;
 
 pub(super) async fn get_peers_and_supporter(
   State(state): State<FoundationState>,
-) -> Result<Json<Arc<Vec<FoundationEntity>>>, StatusCode> {
+) -> Result<Cached<Json<Arc<Vec<FoundationEntity>>>>, StatusCode> {
   match state.peers.get_stats().await {
-    Ok(stats) => Ok(Json(stats)),
+    Ok(stats) => Ok(stats.map(Json)),
     Err(err) => {
       error!("Error while querying peers: {:?}", err);
       Err(StatusCode::INTERNAL_SERVER_ERROR)
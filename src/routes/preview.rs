@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, Path, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::Json;
+
+use crate::blog::{BlogPost, SmallBlogPost};
+use crate::event::{EventPost, SmallEventPost};
+use crate::lang::Language;
+use crate::news::{NewsPost, SmallNewsPost};
+use crate::state::FoundationState;
+
+/// Proof that the request carries the configured preview token as
+/// `Authorization: Bearer <token>`. It is not accepted in the query, where it
+/// would end up in access logs, browser histories and `Referer` headers.
+pub(crate) struct PreviewAccess;
+
+impl FromRequestParts<FoundationState> for PreviewAccess {
+  type Rejection = StatusCode;
+
+  async fn from_request_parts(
+    parts: &mut Parts,
+    state: &FoundationState,
+  ) -> Result<Self, Self::Rejection> {
+    // without a configured token previews are disabled altogether
+    let expected = state.preview_token.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+
+    let token = parts
+      .headers
+      .get(AUTHORIZATION)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix("Bearer "));
+
+    match token {
+      Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(PreviewAccess),
+      _ => Err(StatusCode::UNAUTHORIZED),
+    }
+  }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+pub(crate) async fn list_blog_posts(
+  _: PreviewAccess,
+  State(state): State<FoundationState>,
+  Path(lang): Path<Language>,
+) -> Json<Vec<Arc<SmallBlogPost>>> {
+  Json(state.blog.preview_by_lang(lang).await)
+}
+
+pub(crate) async fn find_blog_post(
+  _: PreviewAccess,
+  State(state): State<FoundationState>,
+  Path((lang, slug)): Path<(Language, String)>,
+) -> Result<Json<Arc<BlogPost>>, StatusCode> {
+  state
+    .blog
+    .preview_by_slug(lang, &slug)
+    .await
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub(crate) async fn list_news_posts(
+  _: PreviewAccess,
+  State(state): State<FoundationState>,
+  Path(lang): Path<Language>,
+) -> Json<Vec<Arc<SmallNewsPost>>> {
+  Json(state.news.preview_by_lang(lang).await)
+}
+
+pub(crate) async fn find_news_post(
+  _: PreviewAccess,
+  State(state): State<FoundationState>,
+  Path((lang, slug)): Path<(Language, String)>,
+) -> Result<Json<Arc<NewsPost>>, StatusCode> {
+  state
+    .news
+    .preview_by_slug(lang, &slug)
+    .await
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub(crate) async fn list_events(
+  _: PreviewAccess,
+  State(state): State<FoundationState>,
+  Path(lang): Path<Language>,
+) -> Json<Vec<Arc<SmallEventPost>>> {
+  Json(state.events.preview_by_lang(lang).await)
+}
+
+pub(crate) async fn find_event(
+  _: PreviewAccess,
+  State(state): State<FoundationState>,
+  Path((lang, slug)): Path<(Language, String)>,
+) -> Result<Json<Arc<EventPost>>, StatusCode> {
+  state
+    .events
+    .preview_by_slug(lang, &slug)
+    .await
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
+}
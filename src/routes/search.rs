@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::blog::SmallBlogPost;
+use crate::event::SmallEventPost;
+use crate::lang::Localized;
+use crate::news::SmallNewsPost;
+use crate::routes::language::RequestLanguage;
+use crate::state::FoundationState;
+
+#[derive(Deserialize)]
+pub(crate) struct SearchQuery {
+  q: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum SearchHit {
+  Blog {
+    score: f32,
+    #[serde(flatten)]
+    post: Arc<SmallBlogPost>,
+  },
+  News {
+    score: f32,
+    #[serde(flatten)]
+    post: Arc<SmallNewsPost>,
+  },
+  Event {
+    score: f32,
+    #[serde(flatten)]
+    post: Arc<SmallEventPost>,
+  },
+}
+
+impl SearchHit {
+  fn score(&self) -> f32 {
+    match self {
+      SearchHit::Blog { score, .. } => *score,
+      SearchHit::News { score, .. } => *score,
+      SearchHit::Event { score, .. } => *score,
+    }
+  }
+}
+
+pub(crate) async fn search(
+  State(state): State<FoundationState>,
+  RequestLanguage(lang): RequestLanguage,
+  Query(query): Query<SearchQuery>,
+) -> Localized<Json<Vec<SearchHit>>> {
+  let blog = state.blog.search(lang, &query.q).await;
+  let news = state.news.search(lang, &query.q).await;
+  let events = state.events.search(lang, &query.q).await;
+
+  let mut hits: Vec<SearchHit> = normalized(blog)
+    .map(|(score, post)| SearchHit::Blog { score, post })
+    .chain(normalized(news).map(|(score, post)| SearchHit::News { score, post }))
+    .chain(normalized(events).map(|(score, post)| SearchHit::Event { score, post }))
+    .collect();
+
+  hits.sort_by(|a, b| b.score().total_cmp(&a.score()));
+
+  Localized::new(lang, Json(hits))
+}
+
+/// Scales the scores of one post type relative to its best hit. Each type has
+/// an index of its own, so raw scores of different types are not comparable.
+fn normalized<T>(hits: Vec<(f32, T)>) -> impl Iterator<Item = (f32, T)> {
+  let best = hits.iter().map(|(score, _)| *score).fold(0.0, f32::max);
+  hits
+    .into_iter()
+    .map(move |(score, post)| (if best > 0.0 { score / best } else { score }, post))
+}
@@ -7,15 +7,16 @@ use axum::http::{Response, StatusCode};
 use axum::Json;
 use reqwest::header::RETRY_AFTER;
 
+use crate::cache::Cached;
 use crate::state::FoundationState;
 use crate::stats::{Series, TimeSelection};
 
 pub(super) async fn get_traffic_stats(
   Path(selection): Path<TimeSelection>,
   State(state): State<FoundationState>,
-) -> Result<Json<Arc<Series<Vec<(f64, f64)>>>>, Response<Body>> {
+) -> Result<Cached<Json<Arc<Series<Vec<(f64, f64)>>>>>, Response<Body>> {
   match state.stats.get_traffic_stats(selection).await {
-    Some(stats) => Ok(Json(stats)),
+    Some(stats) => Ok(stats.map(Json)),
     None => Err(
       Response::builder()
         .status(StatusCode::SERVICE_UNAVAILABLE)
@@ -29,9 +30,9 @@ pub(super) async fn get_traffic_stats(
 pub(super) async fn get_as112_stats(
   Path(selection): Path<TimeSelection>,
   State(state): State<FoundationState>,
-) -> Result<Json<Arc<Series<HashMap<String, Vec<(f64, f64)>>>>>, Response<Body>> {
+) -> Result<Cached<Json<Arc<Series<HashMap<String, Vec<(f64, f64)>>>>>>, Response<Body>> {
   match state.stats.get_as112_stats(selection).await {
-    Some(stats) => Ok(Json(stats)),
+    Some(stats) => Ok(stats.map(Json)),
     None => Err(
       Response::builder()
         .status(StatusCode::SERVICE_UNAVAILABLE)
@@ -1,12 +1,18 @@
-use crate::lang::Language;
+use crate::conditional::Conditional;
+use crate::lang::Localized;
+use crate::routes::language::RequestLanguage;
 use crate::state::FoundationState;
 use crate::team::TeamMember;
-use axum::extract::{Path, State};
+use axum::extract::State;
 use axum::Json;
 
 pub(crate) async fn get_team(
   State(state): State<FoundationState>,
-  Path(lang): Path<Language>,
-) -> Json<Vec<TeamMember<String>>> {
-  Json(state.team.members(&lang))
+  RequestLanguage(lang): RequestLanguage,
+) -> Conditional<Localized<Json<Vec<TeamMember<String>>>>> {
+  state
+    .team
+    .members(&state.languages.chain(lang))
+    .await
+    .map(|members| members.map(Json))
 }
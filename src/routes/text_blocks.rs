@@ -3,18 +3,28 @@ use std::sync::Arc;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::Json;
+use serde::Deserialize;
 
-use crate::lang::Language;
+use crate::lang::Localized;
+use crate::routes::language::RequestLanguage;
 use crate::state::FoundationState;
 use crate::text_blocks::TextBlock;
 
+#[derive(Deserialize)]
+pub(crate) struct TextBlockPath {
+  slug: String,
+}
+
 pub(crate) async fn find_text_block(
   State(state): State<FoundationState>,
-  Path((lang, slug)): Path<(Language, String)>,
-) -> Result<Json<Arc<TextBlock>>, StatusCode> {
-  state
-    .text_blocks
-    .find_text_block(lang, &slug)
-    .map(Json)
-    .ok_or(StatusCode::NOT_FOUND)
+  RequestLanguage(lang): RequestLanguage,
+  Path(path): Path<TextBlockPath>,
+) -> Result<Localized<Json<Arc<TextBlock>>>, StatusCode> {
+  for lang in state.languages.chain(lang) {
+    if let Some(block) = state.text_blocks.find_text_block(lang, &path.slug).await {
+      return Ok(Localized::new(lang, Json(block)));
+    }
+  }
+
+  Err(StatusCode::NOT_FOUND)
 }
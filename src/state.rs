@@ -1,17 +1,29 @@
+use std::sync::Arc;
+
+use metrics_exporter_prometheus::PrometheusHandle;
+use url::Url;
+
 use crate::bird::Bird;
 use crate::blog::Blogs;
 use crate::documents::Documents;
 use crate::event::Events;
+use crate::lang::Languages;
 use crate::looking_glass::LookingGlass;
 use crate::mirrors::Mirrors;
 use crate::news::News;
 use crate::peers::NetworkService;
+use crate::routes::TrustedProxies;
 use crate::stats::Stats;
 use crate::team::Team;
 use crate::text_blocks::TextBlocks;
 
 #[derive(Clone)]
 pub(crate) struct FoundationState {
+  pub(crate) base_url: Arc<Url>,
+  pub(crate) languages: Arc<Languages>,
+  pub(crate) metrics: PrometheusHandle,
+  pub(crate) preview_token: Option<Arc<str>>,
+  pub(crate) trusted_proxies: Arc<TrustedProxies>,
   pub(crate) blog: Blogs,
   pub(crate) news: News,
   pub(crate) text_blocks: TextBlocks,
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
 use url::Url;
 
-use crate::auto_cache::Updater;
+use crate::cache::Updater;
 
 use super::{Series, TimeSelection};
 
@@ -44,6 +44,7 @@ struct PrometheusMetric {
 pub(super) struct As112Updater {
   client: Client,
   prometheus_url: Url,
+  query: String,
   selection: Duration,
 }
 
@@ -62,10 +63,16 @@ impl Updater for As112Updater {
   }
 }
 impl As112Updater {
-  pub(super) fn new(client: Client, prometheus_url: Url, selection: TimeSelection) -> Self {
+  pub(super) fn new(
+    client: Client,
+    prometheus_url: Url,
+    query: String,
+    selection: TimeSelection,
+  ) -> Self {
     Self {
       client,
       prometheus_url,
+      query,
       selection: selection.into(),
     }
   }
@@ -77,7 +84,7 @@ impl As112Updater {
     points: f64,
   ) -> anyhow::Result<Series<HashMap<String, Vec<(f64, f64)>>>> {
     let query = PrometheusQuery {
-      query: "sum by (type) (rate(knot_query_type_total[5m]) or rate(knot_stats_query_type_total[5m])) >= 0.01".to_string(),
+      query: self.query.clone(),
       start,
       end,
       step: ((end - start) / points).as_seconds_f64(),
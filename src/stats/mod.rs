@@ -1,16 +1,17 @@
 mod as112;
 mod traffic;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use time::{Duration, OffsetDateTime};
-use url::Url;
+use tokio_util::sync::CancellationToken;
 
-use crate::auto_cache::Cache;
+use crate::cache::{Cache, CacheStatus, Cached, RefreshPolicy};
+use crate::config::StatsConfig;
 
 use self::{as112::As112Updater, traffic::TrafficUpdater};
 
@@ -24,6 +25,18 @@ pub(crate) enum TimeSelection {
   Year,
 }
 
+impl TimeSelection {
+  fn name(self) -> &'static str {
+    match self {
+      TimeSelection::TwoDays => "two_days",
+      TimeSelection::Week => "week",
+      TimeSelection::Month => "month",
+      TimeSelection::ThreeMonths => "three_months",
+      TimeSelection::Year => "year",
+    }
+  }
+}
+
 impl From<TimeSelection> for Duration {
   fn from(value: TimeSelection) -> Self {
     match value {
@@ -44,7 +57,7 @@ struct TimeSelectionStore<T> {
   year: T,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub(crate) struct Series<T> {
   #[serde(with = "time::serde::rfc3339")]
   start: OffsetDateTime,
@@ -53,6 +66,18 @@ pub(crate) struct Series<T> {
   data: T,
 }
 
+impl<T> TimeSelectionStore<Arc<T>> {
+  fn new(mut create: impl FnMut(TimeSelection) -> T) -> Self {
+    Self {
+      two_days: Arc::new(create(TimeSelection::TwoDays)),
+      week: Arc::new(create(TimeSelection::Week)),
+      month: Arc::new(create(TimeSelection::Month)),
+      three_months: Arc::new(create(TimeSelection::ThreeMonths)),
+      year: Arc::new(create(TimeSelection::Year)),
+    }
+  }
+}
+
 impl<T> TimeSelectionStore<T> {
   pub(crate) fn get(&self, selection: TimeSelection) -> &T {
     match selection {
@@ -67,92 +92,73 @@ impl<T> TimeSelectionStore<T> {
 
 #[derive(Clone)]
 pub(crate) struct Stats {
-  traffic: Arc<TimeSelectionStore<Cache<TrafficUpdater>>>,
-  as112: Arc<TimeSelectionStore<Cache<As112Updater>>>,
+  traffic: Arc<TimeSelectionStore<Arc<Cache<TrafficUpdater>>>>,
+  as112: Arc<TimeSelectionStore<Arc<Cache<As112Updater>>>>,
 }
 
 impl Stats {
-  pub(crate) fn new(prometheus_url: Url) -> Self {
-    let client = Client::new();
+  pub(crate) fn new(client: Client, config: &StatsConfig, state_directory: Option<&Path>) -> Self {
+    let refresh = RefreshPolicy::every(config.refresh_interval);
 
     Self {
-      traffic: Arc::new(TimeSelectionStore {
-        two_days: Cache::new(TrafficUpdater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::TwoDays,
-        )),
-        week: Cache::new(TrafficUpdater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::Week,
-        )),
-        month: Cache::new(TrafficUpdater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::Month,
-        )),
-        three_months: Cache::new(TrafficUpdater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::ThreeMonths,
-        )),
-        year: Cache::new(TrafficUpdater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::Year,
-        )),
-      }),
-      as112: Arc::new(TimeSelectionStore {
-        two_days: Cache::new(As112Updater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::TwoDays,
-        )),
-        week: Cache::new(As112Updater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::Week,
-        )),
-        month: Cache::new(As112Updater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::Month,
-        )),
-        three_months: Cache::new(As112Updater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::ThreeMonths,
-        )),
-        year: Cache::new(As112Updater::new(
-          client.clone(),
-          prometheus_url.clone(),
-          TimeSelection::Year,
-        )),
-      }),
+      traffic: Arc::new(TimeSelectionStore::new(|selection| {
+        Cache::new(
+          format!("traffic {}", selection.name()),
+          refresh,
+          TrafficUpdater::new(
+            client.clone(),
+            config.prometheus_url.clone(),
+            config.traffic_query.clone(),
+            selection,
+          ),
+        )
+        .persist_in(state_directory)
+      })),
+      as112: Arc::new(TimeSelectionStore::new(|selection| {
+        Cache::new(
+          format!("as112 {}", selection.name()),
+          refresh,
+          As112Updater::new(
+            client.clone(),
+            config.prometheus_url.clone(),
+            config.as112_query.clone(),
+            selection,
+          ),
+        )
+        .persist_in(state_directory)
+      })),
     }
   }
 
-  pub(crate) async fn update(&self) -> anyhow::Result<()> {
+  pub(crate) fn status(&self) -> Vec<CacheStatus> {
+    TimeSelection::iter()
+      .flat_map(|selection| {
+        [
+          self.traffic.get(selection).status(),
+          self.as112.get(selection).status(),
+        ]
+      })
+      .collect()
+  }
+
+  pub(crate) fn spawn_refreshers(&self, shutdown: &CancellationToken) {
     for selection in TimeSelection::iter() {
-      self.traffic.get(selection).update().await?;
-      self.as112.get(selection).update().await?;
+      self.traffic.get(selection).spawn_refresher(shutdown);
+      self.as112.get(selection).spawn_refresher(shutdown);
     }
-
-    Ok(())
   }
 
   pub(crate) async fn get_traffic_stats(
     &self,
     selection: TimeSelection,
-  ) -> Option<Arc<Series<Vec<(f64, f64)>>>> {
-    self.traffic.get(selection).get().await
+  ) -> Option<Cached<Arc<Series<Vec<(f64, f64)>>>>> {
+    self.traffic.get(selection).get_cached()
   }
 
   pub(crate) async fn get_as112_stats(
     &self,
     selection: TimeSelection,
-  ) -> Option<Arc<Series<HashMap<String, Vec<(f64, f64)>>>>> {
-    self.as112.get(selection).get().await
+  ) -> Option<Cached<Arc<Series<HashMap<String, Vec<(f64, f64)>>>>>> {
+    self.as112.get(selection).get_cached()
   }
 }
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
 use url::Url;
 
-use crate::auto_cache::Updater;
+use crate::cache::Updater;
 
 use super::{Series, TimeSelection};
 
@@ -39,6 +39,7 @@ struct PrometheusMetrics {
 pub(super) struct TrafficUpdater {
   client: Client,
   prometheus_url: Url,
+  query: String,
   selection: Duration,
 }
 
@@ -58,10 +59,16 @@ impl Updater for TrafficUpdater {
 }
 
 impl TrafficUpdater {
-  pub(super) fn new(client: Client, prometheus_url: Url, selection: TimeSelection) -> Self {
+  pub(super) fn new(
+    client: Client,
+    prometheus_url: Url,
+    query: String,
+    selection: TimeSelection,
+  ) -> Self {
     Self {
       client,
       prometheus_url,
+      query,
       selection: selection.into(),
     }
   }
@@ -73,7 +80,7 @@ impl TrafficUpdater {
     points: f64,
   ) -> anyhow::Result<Series<Vec<(f64, f64)>>> {
     let query = PrometheusQuery {
-      query: "sum(rate(sflow_router_bytes[5m]))*8".to_string(),
+      query: self.query.clone(),
       start,
       end,
       step: ((end - start) / points).as_seconds_f64(),
@@ -0,0 +1,77 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::fd::FromRawFd;
+use std::time::Duration;
+
+use reqwest::Client;
+use sd_notify::NotifyState;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Takes the socket passed by systemd socket activation, if any.
+pub(crate) fn listener() -> anyhow::Result<Option<TcpListener>> {
+  let Some(fd) = sd_notify::listen_fds()?.next() else {
+    return Ok(None);
+  };
+
+  // SAFETY: systemd passes an open listening socket starting at this fd,
+  // which is owned by nothing else in this process
+  let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+  listener.set_nonblocking(true)?;
+  Ok(Some(TcpListener::from_std(listener)?))
+}
+
+/// Tells systemd that the service is ready and starts pinging its watchdog at
+/// half the configured timeout. Every ping requires `/healthz` on `addr` to
+/// answer, so that a server no longer accepting requests is restarted. Does
+/// nothing if not started by systemd.
+pub(crate) fn ready(shutdown: &CancellationToken, client: Client, addr: SocketAddr) {
+  notify(NotifyState::Ready);
+
+  let mut usec = 0;
+  if !sd_notify::watchdog_enabled(false, &mut usec) {
+    return;
+  }
+
+  let interval = Duration::from_micros(usec) / 2;
+  info!("Pinging systemd watchdog every {:?}", interval);
+
+  // a wildcard address is reached through the loopback of its family
+  let ip = match addr.ip() {
+    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+    ip => ip,
+  };
+  let health = format!("http://{}/healthz", SocketAddr::new(ip, addr.port()));
+
+  let shutdown = shutdown.clone();
+  tokio::spawn(async move {
+    shutdown
+      .run_until_cancelled(async {
+        loop {
+          let alive = client
+            .get(&health)
+            .timeout(interval)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+          match alive {
+            Ok(_) => notify(NotifyState::Watchdog),
+            Err(err) => warn!("Skipping watchdog ping, health check failed: {}", err),
+          }
+          tokio::time::sleep(interval).await;
+        }
+      })
+      .await;
+  });
+}
+
+pub(crate) fn stopping() {
+  notify(NotifyState::Stopping);
+}
+
+fn notify(state: NotifyState) {
+  if let Err(err) = sd_notify::notify(false, &[state]) {
+    warn!("Failed to notify systemd: {}", err);
+  }
+}
@@ -1,8 +1,11 @@
-use crate::lang::Language;
+use crate::conditional::{Conditional, ETag, Validators};
+use crate::lang::{Language, Localized};
+use crate::validate::{parse_yaml, read_yaml, ContentError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub(crate) enum WorkingGroup {
@@ -37,35 +40,119 @@ pub(crate) struct TeamMember<T> {
   pub socials: Socials,
 }
 
+type Members = Vec<TeamMember<HashMap<Language, String>>>;
+
+struct TeamFile {
+  members: Members,
+  /// Hash of the team file.
+  etag: ETag,
+}
+
 #[derive(Clone)]
 pub(crate) struct Team {
-  members: Arc<Vec<TeamMember<HashMap<Language, String>>>>,
+  path: Arc<PathBuf>,
+  members: Arc<RwLock<Arc<TeamFile>>>,
 }
 
 impl Team {
   pub(crate) async fn load(path: &Path) -> anyhow::Result<Self> {
     let base_path: PathBuf = path.into();
-    let serialized_teams = tokio::fs::read_to_string(base_path.join("team.yaml")).await?;
-    let team_members = serde_yaml_ng::from_str(&serialized_teams)?;
+    let team_members = read_members(&base_path).await?;
     Ok(Self {
-      members: team_members,
+      path: Arc::new(base_path),
+      members: Arc::new(RwLock::new(team_members)),
     })
   }
 
-  pub(crate) fn members(&self, lang: &Language) -> Vec<TeamMember<String>> {
-    self
-      .members
+  pub(crate) async fn reload(&self) -> anyhow::Result<()> {
+    let team_members = read_members(&self.path).await?;
+    *self.members.write().await = team_members;
+    Ok(())
+  }
+
+  /// Checks the team file and that every member has a description in every
+  /// language.
+  pub(crate) async fn validate(path: &Path) -> Vec<ContentError> {
+    let path = path.join("team.yaml");
+    let members: Members = match read_yaml(&path).await {
+      Ok(members) => members,
+      Err(err) => return vec![err],
+    };
+
+    members
       .iter()
-      .map(|member| TeamMember {
-        name: member.name.clone(),
-        nick: member.nick.clone(),
-        vorstand: member.vorstand,
-        working_groups: member.working_groups.clone(),
-        ripe_handle: member.ripe_handle.clone(),
-        description: member.description.get(lang).unwrap().clone(),
-        image: member.image.clone(),
-        socials: member.socials.clone(),
+      .flat_map(|member| {
+        Language::all()
+          .iter()
+          .copied()
+          .filter(|lang| !member.description.contains_key(lang))
+          .map(|lang| {
+            ContentError::new(
+              &path,
+              None,
+              format!("{} has no {} description", member.name, lang.code()),
+            )
+          })
       })
       .collect()
   }
+
+  /// Returns the members with their description in the first language of
+  /// `langs` it is available in.
+  pub(crate) async fn members(
+    &self,
+    langs: &[Language],
+  ) -> Conditional<Localized<Vec<TeamMember<String>>>> {
+    let team = self.members.read().await.clone();
+    let mut used = Vec::new();
+    let members = team
+      .members
+      .iter()
+      .map(|member| {
+        let description = langs.iter().find_map(|lang| {
+          let description = member.description.get(lang)?;
+          if !used.contains(lang) {
+            used.push(*lang);
+          }
+          Some(description.clone())
+        });
+
+        TeamMember {
+          name: member.name.clone(),
+          nick: member.nick.clone(),
+          vorstand: member.vorstand,
+          working_groups: member.working_groups.clone(),
+          ripe_handle: member.ripe_handle.clone(),
+          description: description.unwrap_or_default(),
+          image: member.image.clone(),
+          socials: member.socials.clone(),
+        }
+      })
+      .collect();
+
+    let validators = Validators {
+      etag: ETag::combine([team.etag, ETag::of(langs)]),
+      last_modified: None,
+    };
+
+    Conditional::new(
+      validators,
+      Localized {
+        langs: used,
+        content: members,
+      },
+    )
+  }
+}
+
+async fn read_members(base_path: &Path) -> Result<Arc<TeamFile>, ContentError> {
+  let path = base_path.join("team.yaml");
+  let content = tokio::fs::read_to_string(&path)
+    .await
+    .map_err(|err| ContentError::new(&path, None, err))?;
+
+  Ok(Arc::new(TeamFile {
+    members: parse_yaml(&path, &content)?,
+    etag: ETag::of(&content),
+  }))
 }
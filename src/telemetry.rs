@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, histogram, Unit};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, info_span, Instrument};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+use crate::config::{LogConfig, LogFormat};
+use crate::routes::PreflightProbe;
+
+const REQUEST_BUCKETS: &[f64] = &[
+  0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+// the looking glass crawl takes several minutes
+const FETCH_BUCKETS: &[f64] = &[
+  0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 1800.0,
+];
+
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest request id accepted from a client or proxy, longer ones are
+/// replaced to keep log lines bounded.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Installs the global subscriber writing log lines to stdout.
+pub(crate) fn init_logging(config: &LogConfig) -> anyhow::Result<()> {
+  let filter = EnvFilter::try_new(&config.filter)
+    .map_err(|err| anyhow::anyhow!("Invalid log filter {}: {}", config.filter, err))?;
+  let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+  match config.format {
+    LogFormat::Compact => builder.compact().try_init(),
+    LogFormat::Json => builder.json().flatten_event(true).try_init(),
+  }
+  .map_err(|err| anyhow::anyhow!("{}", err))
+}
+
+/// Installs the global metrics recorder, the returned handle renders the
+/// metrics in the Prometheus text format.
+pub(crate) fn install(shutdown: &CancellationToken) -> anyhow::Result<PrometheusHandle> {
+  let handle = PrometheusBuilder::new()
+    .set_buckets_for_metric(
+      Matcher::Full("http_request_duration_seconds".to_string()),
+      REQUEST_BUCKETS,
+    )?
+    .set_buckets_for_metric(
+      Matcher::Full("upstream_fetch_duration_seconds".to_string()),
+      FETCH_BUCKETS,
+    )?
+    .install_recorder()?;
+
+  describe_counter!(
+    "http_requests_total",
+    "Handled requests by route and status"
+  );
+  describe_histogram!(
+    "http_request_duration_seconds",
+    Unit::Seconds,
+    "Time spent handling requests by route"
+  );
+  describe_counter!(
+    "cache_requests_total",
+    "Cache lookups by result (hit, stale or miss)"
+  );
+  describe_counter!(
+    "cache_refreshes_total",
+    "Cache refreshes by result (success or failure)"
+  );
+  describe_histogram!(
+    "upstream_fetch_duration_seconds",
+    Unit::Seconds,
+    "Time spent fetching data from upstream sources"
+  );
+  describe_gauge!("content_posts", "Published posts by type and language");
+
+  let upkeep = handle.clone();
+  let shutdown = shutdown.clone();
+  tokio::spawn(async move {
+    shutdown
+      .run_until_cancelled(async {
+        loop {
+          tokio::time::sleep(UPKEEP_INTERVAL).await;
+          upkeep.run_upkeep();
+        }
+      })
+      .await;
+  });
+
+  Ok(handle)
+}
+
+/// Takes the `X-Request-Id` of a proxy in front of the api or generates one,
+/// and attaches it to the response and to every log line of the request.
+pub(crate) async fn request_id(mut request: Request, next: Next) -> Response {
+  let id = request
+    .headers()
+    .get(&X_REQUEST_ID)
+    .filter(|id| is_valid_request_id(id))
+    .cloned()
+    .unwrap_or_else(|| {
+      HeaderValue::try_from(Uuid::new_v4().to_string()).expect("uuids are valid header values")
+    });
+  request.headers_mut().insert(&X_REQUEST_ID, id.clone());
+
+  let span = info_span!("request", id = id.to_str().unwrap_or_default());
+  let mut response = next.run(request).instrument(span).await;
+  response.headers_mut().insert(&X_REQUEST_ID, id);
+  response
+}
+
+fn is_valid_request_id(id: &HeaderValue) -> bool {
+  !id.is_empty()
+    && id.len() <= MAX_REQUEST_ID_LENGTH
+    && id.as_bytes().iter().all(u8::is_ascii_graphic)
+}
+
+/// Records count and latency of every request by its route pattern, e.g.
+/// `/blog/{lang}/{slug}`, to keep the number of label values bounded, and
+/// writes an access log line.
+pub(crate) async fn track_requests(request: Request, next: Next) -> Response {
+  // the router always rejects the probe, counting it would look like errors
+  if request.extensions().get::<PreflightProbe>().is_some() {
+    return next.run(request).await;
+  }
+
+  let started = Instant::now();
+  let method = request.method().to_string();
+  let path = request.uri().path().to_string();
+  let route = request
+    .extensions()
+    .get::<MatchedPath>()
+    .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+
+  let response = next.run(request).await;
+  let latency = started.elapsed();
+
+  info!(
+    method,
+    route,
+    path,
+    status = response.status().as_u16(),
+    latency_ms = latency.as_secs_f64() * 1000.0,
+    "Handled request"
+  );
+
+  counter!(
+    "http_requests_total",
+    "method" => method.clone(),
+    "route" => route.clone(),
+    "status" => response.status().as_u16().to_string(),
+  )
+  .increment(1);
+  histogram!(
+    "http_request_duration_seconds",
+    "method" => method,
+    "route" => route,
+  )
+  .record(latency.as_secs_f64());
+
+  response
+}
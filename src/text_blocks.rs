@@ -1,16 +1,21 @@
-use std::path::Path;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::anyhow;
 use regex::{Captures, Regex, Replacer};
 use serde::Serialize;
+use tokio::sync::RwLock;
 use url::Url;
 
 use crate::lang::Language;
+use crate::validate::{list_files, ContentError, ContentErrors};
 
 #[derive(Debug, Clone)]
 pub(crate) struct TextBlocks {
-  blocks: Arc<Vec<Arc<TextBlock>>>,
+  directory: Arc<PathBuf>,
+  base_url: Arc<Url>,
+  blocks: Arc<RwLock<Arc<Vec<Arc<TextBlock>>>>>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -37,41 +42,111 @@ impl Replacer for AddressReplacer {
 
 impl TextBlocks {
   pub(crate) async fn load(directory: &Path, base_url: &Url) -> anyhow::Result<Self> {
-    let mut blocks = Vec::new();
+    let blocks = read_blocks(directory, base_url).await?;
 
-    let mut dir = tokio::fs::read_dir(directory).await?;
-    while let Some(entry) = dir.next_entry().await? {
-      if entry.file_type().await?.is_dir() {
-        continue;
-      }
+    Ok(TextBlocks {
+      directory: Arc::new(directory.to_path_buf()),
+      base_url: Arc::new(base_url.clone()),
+      blocks: Arc::new(RwLock::new(Arc::new(blocks))),
+    })
+  }
 
-      let path = entry.path();
-      let body = tokio::fs::read_to_string(path.as_path()).await?;
+  pub(crate) async fn reload(&self) -> anyhow::Result<()> {
+    let blocks = read_blocks(&self.directory, &self.base_url).await?;
+    *self.blocks.write().await = Arc::new(blocks);
+    Ok(())
+  }
 
-      let file_name = path.file_stem().unwrap().to_str().unwrap();
-      let (lang, slug) = parse_file_name(file_name)?;
+  /// Checks every text block of the directory and that each text block is
+  /// translated into every language.
+  pub(crate) async fn validate(directory: &Path, base_url: &Url) -> Vec<ContentError> {
+    let (blocks, mut errors) = read_files(directory, base_url).await;
+
+    let mut translations: BTreeMap<&str, HashSet<Language>> = BTreeMap::new();
+    for block in &blocks {
+      translations
+        .entry(&block.slug)
+        .or_default()
+        .insert(block.lang);
+    }
 
-      blocks.push(Arc::new(TextBlock {
-        slug: slug.to_string(),
-        lang,
-        body: parse_markdown(&body, base_url.join("/text-blocks/assets/")?)?,
-      }));
+    for (slug, langs) in translations {
+      for lang in Language::all()
+        .iter()
+        .copied()
+        .filter(|lang| !langs.contains(lang))
+      {
+        errors.push(ContentError::new(
+          directory,
+          None,
+          format!("Text block {} has no {} translation", slug, lang.code()),
+        ));
+      }
     }
 
-    Ok(TextBlocks {
-      blocks: Arc::new(blocks),
-    })
+    errors
   }
 
-  pub(crate) fn find_text_block(&self, lang: Language, slug: &str) -> Option<Arc<TextBlock>> {
+  pub(crate) async fn find_text_block(&self, lang: Language, slug: &str) -> Option<Arc<TextBlock>> {
     self
       .blocks
+      .read()
+      .await
       .iter()
       .find(|block| block.lang == lang && block.slug == slug)
       .cloned()
   }
 }
 
+async fn read_blocks(
+  directory: &Path,
+  base_url: &Url,
+) -> Result<Vec<Arc<TextBlock>>, ContentErrors> {
+  let (blocks, errors) = read_files(directory, base_url).await;
+  if !errors.is_empty() {
+    return Err(ContentErrors(errors));
+  }
+
+  Ok(blocks)
+}
+
+/// Reads all text blocks of the directory, collecting the problems of every
+/// file instead of stopping at the first one.
+async fn read_files(directory: &Path, base_url: &Url) -> (Vec<Arc<TextBlock>>, Vec<ContentError>) {
+  let mut blocks = Vec::new();
+  let mut errors = Vec::new();
+
+  let paths = match list_files(directory).await {
+    Ok(paths) => paths,
+    Err(err) => return (blocks, vec![ContentError::new(directory, None, err)]),
+  };
+
+  for path in paths {
+    match read_block(&path, base_url).await {
+      Ok(block) => blocks.push(Arc::new(block)),
+      Err(err) => errors.push(ContentError::new(&path, None, err)),
+    }
+  }
+
+  (blocks, errors)
+}
+
+async fn read_block(path: &Path, base_url: &Url) -> anyhow::Result<TextBlock> {
+  let file_name = path
+    .file_stem()
+    .and_then(|file_name| file_name.to_str())
+    .ok_or_else(|| anyhow!("File name is not valid UTF-8"))?;
+  let (lang, slug) = parse_file_name(file_name)?;
+
+  let body = tokio::fs::read_to_string(path).await?;
+
+  Ok(TextBlock {
+    slug: slug.to_string(),
+    lang,
+    body: parse_markdown(&body, base_url.join("/text-blocks/assets/")?)?,
+  })
+}
+
 fn parse_file_name(file_name: &str) -> anyhow::Result<(Language, &str)> {
   let (slug, lang) = file_name
     .rsplit_once('.')
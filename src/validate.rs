@@ -0,0 +1,117 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use crate::blog::Blogs;
+use crate::documents::Documents;
+use crate::event::Events;
+use crate::mirrors::Mirrors;
+use crate::news::News;
+use crate::peers::NetworkService;
+use crate::team::Team;
+use crate::text_blocks::TextBlocks;
+
+/// A problem with a single file of the content directory.
+#[derive(Debug)]
+pub(crate) struct ContentError {
+  path: PathBuf,
+  line: Option<usize>,
+  message: String,
+}
+
+/// All problems found while loading a part of the content directory.
+#[derive(Debug)]
+pub(crate) struct ContentErrors(pub(crate) Vec<ContentError>);
+
+impl ContentError {
+  pub(crate) fn new(path: &Path, line: Option<usize>, message: impl Display) -> Self {
+    Self {
+      path: path.to_path_buf(),
+      line,
+      message: message.to_string(),
+    }
+  }
+}
+
+impl Display for ContentError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self.line {
+      Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+      None => write!(f, "{}: {}", self.path.display(), self.message),
+    }
+  }
+}
+
+impl std::error::Error for ContentError {}
+
+impl Display for ContentErrors {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for (i, error) in self.0.iter().enumerate() {
+      if i > 0 {
+        writeln!(f)?;
+      }
+      write!(f, "{}", error)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for ContentErrors {}
+
+impl From<ContentError> for ContentErrors {
+  fn from(error: ContentError) -> Self {
+    ContentErrors(vec![error])
+  }
+}
+
+/// Reads and deserializes a yaml file, reporting the line of syntax errors.
+pub(crate) async fn read_yaml<T: DeserializeOwned>(path: &Path) -> Result<T, ContentError> {
+  let content = tokio::fs::read_to_string(path)
+    .await
+    .map_err(|err| ContentError::new(path, None, err))?;
+
+  parse_yaml(path, &content)
+}
+
+/// Parses yaml that was already read from `path`.
+pub(crate) fn parse_yaml<T: DeserializeOwned>(
+  path: &Path,
+  content: &str,
+) -> Result<T, ContentError> {
+  serde_yaml_ng::from_str(content)
+    .map_err(|err| ContentError::new(path, err.location().map(|location| location.line()), err))
+}
+
+/// Lists the files of a directory, ignoring subdirectories like `assets`.
+pub(crate) async fn list_files(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+
+  let mut dir = tokio::fs::read_dir(directory).await?;
+  while let Some(entry) = dir.next_entry().await? {
+    if !entry.file_type().await?.is_dir() {
+      files.push(entry.path());
+    }
+  }
+
+  files.sort();
+  Ok(files)
+}
+
+/// Loads the whole content directory the same way the server does and
+/// collects every problem instead of stopping at the first one.
+pub(crate) async fn validate(content_directory: &Path, base_url: &Url) -> Vec<ContentError> {
+  let mut errors = Vec::new();
+
+  errors.append(&mut Blogs::validate(&content_directory.join("blog")).await);
+  errors.append(&mut News::validate(&content_directory.join("news")).await);
+  errors.append(&mut Events::validate(&content_directory.join("event")).await);
+  errors.append(&mut TextBlocks::validate(&content_directory.join("text_blocks"), base_url).await);
+  errors.append(&mut Documents::validate(&content_directory.join("documents")).await);
+  errors.append(&mut Team::validate(&content_directory.join("team")).await);
+  errors.append(&mut Mirrors::validate(&content_directory.join("mirrors.yaml")).await);
+  errors.append(&mut NetworkService::validate(&content_directory.join("supporter")).await);
+
+  errors
+}
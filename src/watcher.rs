@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use crate::state::FoundationState;
+
+/// Time to wait for further file system events before reloading, so that a
+/// deploy touching many files only triggers a single reload per section.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Section {
+  Blog,
+  News,
+  Events,
+  TextBlocks,
+  Documents,
+  Team,
+  Mirrors,
+}
+
+impl Section {
+  const ALL: [Section; 7] = [
+    Section::Blog,
+    Section::News,
+    Section::Events,
+    Section::TextBlocks,
+    Section::Documents,
+    Section::Team,
+    Section::Mirrors,
+  ];
+
+  fn from_path(content_directory: &Path, path: &Path) -> Option<Self> {
+    let mut components = path
+      .strip_prefix(content_directory)
+      .ok()?
+      .components()
+      .filter_map(|component| match component {
+        Component::Normal(name) => name.to_str(),
+        _ => None,
+      });
+
+    let section = match components.next()? {
+      "blog" => Section::Blog,
+      "news" => Section::News,
+      "event" => Section::Events,
+      "text_blocks" => Section::TextBlocks,
+      "documents" => Section::Documents,
+      "team" => Section::Team,
+      "mirrors.yaml" => Section::Mirrors,
+      _ => return None,
+    };
+
+    // assets and downloads are served straight from disk
+    match components.next() {
+      Some("assets") | Some("download") => None,
+      _ => Some(section),
+    }
+  }
+
+  async fn reload(self, state: &FoundationState) -> anyhow::Result<()> {
+    match self {
+      Section::Blog => state.blog.reload().await,
+      Section::News => state.news.reload().await,
+      Section::Events => state.events.reload().await,
+      Section::TextBlocks => state.text_blocks.reload().await,
+      Section::Documents => state.documents.reload().await,
+      Section::Team => state.team.reload().await,
+      Section::Mirrors => state.mirrors.reload().await,
+    }
+  }
+}
+
+/// Watches the content directory and reloads the affected parts of the state
+/// whenever files below it change, until `shutdown` is cancelled.
+///
+/// The directory is watched by its configured path, not where it points to,
+/// so that a deploy switching a symlink to a new release is picked up.
+pub(crate) fn watch(
+  content_directory: PathBuf,
+  state: FoundationState,
+  shutdown: CancellationToken,
+) -> anyhow::Result<()> {
+  let content_directory = std::path::absolute(content_directory)?;
+  let (tx, mut rx) = mpsc::unbounded_channel();
+
+  let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |event| {
+    let _ = tx.send(event);
+  })?;
+  watcher.watch(&content_directory, RecursiveMode::Recursive)?;
+  // replacing the directory or the symlink to it is only seen from its parent
+  if let Some(parent) = content_directory.parent() {
+    watcher.watch(parent, RecursiveMode::NonRecursive)?;
+  }
+
+  info!("Watching {} for changes...", content_directory.display());
+
+  tokio::spawn(async move {
+    while let Some(Some(event)) = shutdown.run_until_cancelled(rx.recv()).await {
+      let mut changes = Changes::default();
+      changes.collect(&content_directory, event);
+
+      tokio::time::sleep(DEBOUNCE).await;
+      while let Ok(event) = rx.try_recv() {
+        changes.collect(&content_directory, event);
+      }
+
+      if changes.replaced {
+        info!(
+          "{} was replaced, watching the new version...",
+          content_directory.display()
+        );
+        // the previous watch follows the old target, if it still exists
+        let _ = watcher.unwatch(&content_directory);
+        if let Err(err) = watcher.watch(&content_directory, RecursiveMode::Recursive) {
+          error!(
+            "Cannot watch {} for changes: {:#}",
+            content_directory.display(),
+            err
+          );
+        }
+        changes.sections.extend(Section::ALL);
+      }
+
+      for section in changes.sections {
+        match section.reload(&state).await {
+          Ok(()) => info!("Reloaded {:?} content", section),
+          Err(err) => error!(
+            "Failed to reload {:?} content, keeping previous version: {:#}",
+            section, err
+          ),
+        }
+      }
+    }
+  });
+
+  Ok(())
+}
+
+/// Changes seen within one debounce interval.
+#[derive(Default)]
+struct Changes {
+  sections: HashSet<Section>,
+  /// The content directory itself was replaced, e.g. by switching a symlink.
+  replaced: bool,
+}
+
+impl Changes {
+  fn collect(&mut self, content_directory: &Path, event: notify::Result<Event>) {
+    let event = match event {
+      Ok(event) => event,
+      Err(err) => {
+        error!("Error while watching content directory: {:#}", err);
+        return;
+      }
+    };
+
+    if matches!(event.kind, EventKind::Access(_)) {
+      return;
+    }
+
+    for path in &event.paths {
+      if path == content_directory {
+        self.replaced = true;
+      } else if let Some(section) = Section::from_path(content_directory, path) {
+        self.sections.insert(section);
+      }
+    }
+  }
+}